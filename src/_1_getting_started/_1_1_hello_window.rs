@@ -1,6 +1,7 @@
 extern crate glfw;
 
-use self::glfw::{Context, Key, Action};
+use self::glfw::{Key, Action};
+use context::{self, Window};
 
 extern crate gl;
 
@@ -13,7 +14,7 @@ const SCR_HEIGHT: u32 = 600;
 pub fn main_1_1_1() {
     // glfw: initialize and configure
     // ------------------------------
-    let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
+    let mut glfw = context::init();
     glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
     glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
     #[cfg(target_os = "macos")]
@@ -47,7 +48,7 @@ pub fn main_1_1_1() {
 }
 
 // NOTE: not the same version as in common.rs!
-fn process_events(window: &mut glfw::Window, events: &Receiver<(f64, glfw::WindowEvent)>) {
+fn process_events(window: &mut Window, events: &Receiver<(f64, glfw::WindowEvent)>) {
     for (_, event) in glfw::flush_messages(events) {
         match event {
             glfw::WindowEvent::FramebufferSize(width, height) => {
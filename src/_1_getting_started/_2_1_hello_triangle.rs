@@ -1,7 +1,8 @@
 #![allow(non_upper_case_globals)]
 extern crate glfw;
 
-use self::glfw::{Context, Key, Action};
+use self::glfw::{Key, Action};
+use context::{self, Window};
 
 extern crate gl;
 
@@ -38,7 +39,7 @@ const fragmentShaderSource: &str = r#"
 pub fn main_1_2_1() {
     // glfw: initialize and configure
     // ------------------------------
-    let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
+    let mut glfw = context::init();
     glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
     glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
     #[cfg(target_os = "macos")]
@@ -166,7 +167,7 @@ pub fn main_1_2_1() {
 }
 
 // NOTE: not the same version as in common.rs!
-fn process_events(window: &mut glfw::Window, events: &Receiver<(f64, glfw::WindowEvent)>) {
+fn process_events(window: &mut Window, events: &Receiver<(f64, glfw::WindowEvent)>) {
     for (_, event) in glfw::flush_messages(events) {
         match event {
             glfw::WindowEvent::FramebufferSize(width, height) => {
@@ -1,7 +1,8 @@
 #![allow(non_upper_case_globals)]
 extern crate glfw;
 
-use self::glfw::{Context, Key, Action};
+use self::glfw::{Key, Action};
+use context::{self, Window};
 
 extern crate gl;
 
@@ -22,7 +23,6 @@ use image::GenericImageView;
 
 use cgmath::{Matrix4, vec3, Rad};
 use cgmath::prelude::*;
-use self::glfw::ffi::glfwGetTime;
 
 // settings
 const SCR_WIDTH: u32 = 800;
@@ -32,7 +32,7 @@ const SCR_HEIGHT: u32 = 600;
 pub fn main_1_5_2() {
     // glfw: initialize and configure
     // ------------------------------
-    let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
+    let mut glfw = context::init();
     glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
     glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
     #[cfg(target_os = "macos")]
@@ -194,7 +194,7 @@ pub fn main_1_5_2() {
 
             transform = Matrix4::identity();
             transform = transform * Matrix4::<f32>::from_translation(vec3(-0.5, 0.5, 0.0));
-            let scaleAmount = glfwGetTime().sin() as f32;
+            let scaleAmount = glfw.get_time().sin() as f32;
             transform = transform * Matrix4::<f32>::from_scale(scaleAmount);
             gl::UniformMatrix4fv(transformLoc, 1, gl::FALSE, transform.as_ptr());
 
@@ -216,7 +216,7 @@ pub fn main_1_5_2() {
     }
 }
 
-fn process_events(window: &mut glfw::Window, events: &Receiver<(f64, glfw::WindowEvent)>) {
+fn process_events(window: &mut Window, events: &Receiver<(f64, glfw::WindowEvent)>) {
     for (_, event) in glfw::flush_messages(events) {
         match event {
             glfw::WindowEvent::FramebufferSize(width, height) => {
@@ -3,7 +3,8 @@
 
 extern crate glfw;
 
-use self::glfw::{Context, Key, Action};
+use self::glfw::{Key, Action};
+use context::{self, Window};
 
 extern crate gl;
 
@@ -50,7 +51,7 @@ pub fn main_1_7_2() {
 
     // glfw: initialize and configure
     // ------------------------------
-    let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
+    let mut glfw = context::init();
     glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
     glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
     #[cfg(target_os = "macos")]
@@ -297,7 +298,7 @@ fn process_events(events: &Receiver<(f64, glfw::WindowEvent)>) {
 }
 
 /// NOTE: not the same function as the one in common.rs!
-fn processInput(window: &mut glfw::Window, deltaTime: f32, cameraPos: &mut Point3<f32>) {
+fn processInput(window: &mut Window, deltaTime: f32, cameraPos: &mut Point3<f32>) {
     if window.get_key(Key::Escape) == Action::Press {
         window.set_should_close(true)
     }
@@ -3,7 +3,8 @@
 
 extern crate glfw;
 
-use self::glfw::{Context, Key, Action};
+use self::glfw::{Key, Action};
+use context::{self, Window};
 
 extern crate gl;
 
@@ -57,7 +58,7 @@ pub fn main_1_7_3() {
 
     // glfw: initialize and configure
     // ------------------------------
-    let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
+    let mut glfw = context::init();
     glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
     glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
     #[cfg(target_os = "macos")]
@@ -368,7 +369,7 @@ fn process_events(events: &Receiver<(f64, glfw::WindowEvent)>,
 }
 
 /// NOTE: not the same function as the one in common.rs!
-fn processInput(window: &mut glfw::Window,
+fn processInput(window: &mut Window,
                 deltaTime: f32,
                 cameraPos: &mut Point3<f32>,
                 cameraFront: &mut Vector3<f32>) {
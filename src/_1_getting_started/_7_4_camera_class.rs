@@ -3,7 +3,7 @@
 
 extern crate glfw;
 
-use self::glfw::Context;
+use context;
 
 extern crate gl;
 
@@ -23,7 +23,7 @@ use image;
 use image::GenericImage;
 use image::GenericImageView;
 
-use cgmath::{Matrix4, Vector3, vec3, Deg, perspective, Point3};
+use cgmath::{Matrix4, Vector3, vec3, Deg, Point3};
 use cgmath::prelude::*;
 
 // settings
@@ -46,7 +46,7 @@ pub fn main_1_7_4() {
 
     // glfw: initialize and configure
     // ------------------------------
-    let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
+    let mut glfw = context::init();
     glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
     glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
     #[cfg(target_os = "macos")]
@@ -246,7 +246,7 @@ pub fn main_1_7_4() {
             ourShader.useProgram();
 
             // pass projection matrix to shader (note that in this case it could change every frame)
-            let projection: Matrix4<f32> = perspective(Deg(camera.Zoom), SCR_WIDTH as f32 / SCR_HEIGHT as f32, 0.1, 100.0);
+            let projection: Matrix4<f32> = camera.GetProjectionMatrix(SCR_WIDTH as f32 / SCR_HEIGHT as f32);
             ourShader.setMat4(c_str!("projection"), &projection);
 
             // camera/view transformation
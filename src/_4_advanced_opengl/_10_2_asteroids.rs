@@ -2,21 +2,23 @@
 #![allow(non_snake_case)]
 extern crate glfw;
 
-use self::glfw::Context;
+use context;
 
 extern crate gl;
 extern crate rand;
 
-use self::rand::Rng;
+use self::rand::{Rng, SeedableRng};
+use self::rand::rngs::StdRng;
 
 use std::ffi::CStr;
 
 use common::{process_events, processInput};
 use shader::Shader;
-use camera::Camera;
+use camera::{Camera, Projection};
+use utils;
 use model::Model;
 
-use cgmath::{Matrix4, vec3, Point3, Deg, perspective};
+use cgmath::{Matrix4, vec3, Point3, Deg};
 use cgmath::prelude::*;
 
 // settings
@@ -26,6 +28,9 @@ const SCR_HEIGHT: u32 = 720;
 pub fn main_4_10_2() {
     let mut camera = Camera {
         Position: Point3::new(0.0, 0.0, 55.0),
+        Target: Point3::new(0.0, -3.0, 0.0),
+        // the asteroid field is too deep for a regular depth range
+        Projection: Projection::ReversedZ { near: 0.1 },
         ..Camera::default()
     };
 
@@ -39,7 +44,7 @@ pub fn main_4_10_2() {
 
     // glfw: initialize and configure
     // ------------------------------
-    let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
+    let mut glfw = context::init();
     glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
     glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
     #[cfg(target_os = "macos")]
@@ -66,6 +71,7 @@ pub fn main_4_10_2() {
         // configure global opengl state
         // -----------------------------
         gl::Enable(gl::DEPTH_TEST);
+        utils::set_reversed_z(true);
 
         // build and compile shaders
         // -------------------------
@@ -82,7 +88,7 @@ pub fn main_4_10_2() {
         // ------------------------------------------------------------------
         let amount = 1000;
         let mut modelMatrices: Vec<Matrix4<f32>> = Vec::with_capacity(amount);
-        let mut rng = rand::thread_rng();
+        let mut rng = StdRng::seed_from_u64(glfw.get_time() as u64); // initialize random seed
         let radius = 50.0;
         let offset: f32 = 2.5;
         for i in 0..amount {
@@ -134,7 +140,7 @@ pub fn main_4_10_2() {
             gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
 
             // configure transformation matrices
-            let projection: Matrix4<f32> = perspective(Deg(camera.Zoom), SCR_WIDTH as f32 / SCR_HEIGHT as f32, 0.1, 1000.0);
+            let projection = camera.GetProjectionMatrix(SCR_WIDTH as f32 / SCR_HEIGHT as f32);
             let view = camera.GetViewMatrix();
             shader.useProgram();
             shader.setMat4(c_str!("projection"), &projection);
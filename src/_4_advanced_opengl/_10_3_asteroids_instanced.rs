@@ -2,12 +2,13 @@
 #![allow(non_snake_case)]
 extern crate glfw;
 
-use self::glfw::Context;
+use context;
 
 extern crate gl;
 extern crate rand;
 
-use self::rand::Rng;
+use self::rand::{Rng, SeedableRng};
+use self::rand::rngs::StdRng;
 
 use std::ffi::CStr;
 use std::mem;
@@ -16,10 +17,11 @@ use std::ptr;
 
 use common::{process_events, processInput};
 use shader::Shader;
-use camera::Camera;
+use camera::{Camera, Projection};
+use utils;
 use model::Model;
 
-use cgmath::{Matrix4, vec3, Point3, Vector4, Deg, perspective};
+use cgmath::{Matrix4, vec3, Point3, Vector4, Deg};
 use cgmath::prelude::*;
 
 // settings
@@ -29,6 +31,9 @@ const SCR_HEIGHT: u32 = 720;
 pub fn main_4_10_3() {
     let mut camera = Camera {
         Position: Point3::new(0.0, 0.0, 155.0),
+        Target: Point3::new(0.0, -3.0, 0.0),
+        // the asteroid field is too deep for a regular depth range
+        Projection: Projection::ReversedZ { near: 0.1 },
         ..Camera::default()
     };
 
@@ -42,7 +47,7 @@ pub fn main_4_10_3() {
 
     // glfw: initialize and configure
     // ------------------------------
-    let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
+    let mut glfw = context::init();
     glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
     glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
     #[cfg(target_os = "macos")]
@@ -65,10 +70,11 @@ pub fn main_4_10_3() {
     // ---------------------------------------
     gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
 
-    let (asteroidShader, planetShader, rock, planet, amount) = unsafe {
+    let (asteroidShader, planetShader, rock, planet, amount, modelMatrices, bounds, buffer) = unsafe {
         // configure global opengl state
         // -----------------------------
         gl::Enable(gl::DEPTH_TEST);
+        utils::set_reversed_z(true);
 
         // build and compile shaders
         // -------------------------
@@ -88,7 +94,12 @@ pub fn main_4_10_3() {
         // ------------------------------------------------------------------
         let amount = 100_000;
         let mut modelMatrices: Vec<Matrix4<f32>> = Vec::with_capacity(amount);
-        let mut rng = rand::thread_rng();
+        // bounding sphere of each asteroid for frustum culling
+        let mut bounds: Vec<(Point3<f32>, f32)> = Vec::with_capacity(amount);
+        let rockRadius = rock.meshes.iter()
+            .flat_map(|mesh| mesh.vertices.iter())
+            .fold(0.0f32, |radius, vertex| radius.max(vertex.Position.magnitude()));
+        let mut rng = StdRng::seed_from_u64(glfw.get_time() as u64); // initialize random seed
         let radius = 150.0;
         let offset: f32 = 25.0;
         for i in 0..amount {
@@ -111,6 +122,7 @@ pub fn main_4_10_3() {
 
             // 4. now add to list of matrices
             modelMatrices.push(model);
+            bounds.push((Point3::new(x, y, z), scale * rockRadius));
         }
 
         // configure instanced array
@@ -122,7 +134,7 @@ pub fn main_4_10_3() {
             gl::ARRAY_BUFFER,
             (amount * mem::size_of::<Matrix4<f32>>()) as isize,
             &modelMatrices[0] as *const Matrix4<f32> as *const c_void,
-            gl::STATIC_DRAW);
+            gl::DYNAMIC_DRAW); // only the visible asteroids are uploaded each frame
 
         // set transformation matrices as an instance vertex attribute (with divisor 1)
         // note: we're cheating a little by taking the, now publicly declared, VAO of the model's mesh(es) and adding new vertexAttribPointers
@@ -151,8 +163,10 @@ pub fn main_4_10_3() {
             gl::BindVertexArray(0);
         }
 
-        (asteroidShader, planetShader, rock, planet, amount)
+        (asteroidShader, planetShader, rock, planet, amount, modelMatrices, bounds, buffer)
     };
+    let mut visibleMatrices: Vec<Matrix4<f32>> = Vec::with_capacity(amount);
+    let mut reportTime = 0.0;
 
     // render loop
     // -----------
@@ -178,7 +192,7 @@ pub fn main_4_10_3() {
             gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
 
             // configure transformation matrices
-            let projection: Matrix4<f32> = perspective(Deg(45.0), SCR_WIDTH as f32 / SCR_HEIGHT as f32, 0.1, 1000.0);
+            let projection = camera.GetProjectionMatrix(SCR_WIDTH as f32 / SCR_HEIGHT as f32);
             let view = camera.GetViewMatrix();
             asteroidShader.useProgram();
             asteroidShader.setMat4(c_str!("projection"), &projection);
@@ -193,6 +207,25 @@ pub fn main_4_10_3() {
             planetShader.setMat4(c_str!("model"), &model);
             planet.Draw(&planetShader);
 
+            // cull the asteroids outside of the view frustum and upload the matrices of the remaining ones
+            let frustum = camera.GetFrustum(SCR_WIDTH as f32 / SCR_HEIGHT as f32);
+            visibleMatrices.clear();
+            visibleMatrices.extend(modelMatrices.iter().zip(&bounds)
+                .filter(|&(_, &(center, radius))| frustum.intersects_sphere(center, radius))
+                .map(|(model, _)| *model));
+            if !visibleMatrices.is_empty() {
+                gl::BindBuffer(gl::ARRAY_BUFFER, buffer);
+                gl::BufferSubData(
+                    gl::ARRAY_BUFFER,
+                    0,
+                    (visibleMatrices.len() * mem::size_of::<Matrix4<f32>>()) as isize,
+                    &visibleMatrices[0] as *const Matrix4<f32> as *const c_void);
+            }
+            if currentFrame >= reportTime {
+                println!("{} of {} asteroids culled", amount - visibleMatrices.len(), amount);
+                reportTime = currentFrame + 1.0;
+            }
+
             // draw meteorites
             asteroidShader.useProgram();
             asteroidShader.setInt(c_str!("texture_diffuse1"), 0);
@@ -201,7 +234,7 @@ pub fn main_4_10_3() {
 
             for mesh in &rock.meshes {
                 gl::BindVertexArray(mesh.VAO);
-                gl::DrawElementsInstanced(gl::TRIANGLES, mesh.indices.len() as i32, gl::UNSIGNED_INT, ptr::null(), amount as i32);
+                gl::DrawElementsInstanced(gl::TRIANGLES, mesh.indices.len() as i32, gl::UNSIGNED_INT, ptr::null(), visibleMatrices.len() as i32);
                 gl::BindVertexArray(0);
             }
         }
@@ -3,7 +3,7 @@
 
 extern crate glfw;
 
-use self::glfw::Context;
+use context;
 
 extern crate gl;
 
@@ -18,7 +18,7 @@ use common::{process_events, processInput};
 use shader::Shader;
 use camera::Camera;
 
-use cgmath::{Matrix4, Deg, perspective, Point3};
+use cgmath::{Matrix4, Point3};
 use cgmath::prelude::*;
 
 // settings
@@ -41,7 +41,7 @@ pub fn main_4_11() {
 
     // glfw: initialize and configure
     // ------------------------------
-    let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
+    let mut glfw = context::init();
     glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
     glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
     #[cfg(target_os = "macos")]
@@ -239,7 +239,7 @@ pub fn main_4_11() {
 
             // set transformation matrices
             shader.useProgram();
-            let projection: Matrix4<f32> = perspective(Deg(camera.Zoom), SCR_WIDTH as f32 / SCR_HEIGHT as f32, 0.1, 100.0);
+            let projection: Matrix4<f32> = camera.GetProjectionMatrix(SCR_WIDTH as f32 / SCR_HEIGHT as f32);
             shader.setMat4(c_str!("projection"), &projection);
             shader.setMat4(c_str!("view"), &camera.GetViewMatrix());
             shader.setMat4(c_str!("model"), &Matrix4::identity());
@@ -9,13 +9,13 @@ use std::ffi::CStr;
 
 extern crate glfw;
 
-use self::glfw::Context;
+use context;
 
 extern crate gl;
 
 use self::gl::types::*;
 
-use cgmath::{Matrix4, Deg, perspective, Point3};
+use cgmath::{Matrix4, Point3};
 use cgmath::prelude::*;
 
 use image;
@@ -46,7 +46,7 @@ pub fn main_4_6_1() {
 
     // glfw: initialize and configure
     // ------------------------------
-    let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
+    let mut glfw = context::init();
     glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
     glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
     #[cfg(target_os = "macos")]
@@ -246,7 +246,7 @@ pub fn main_4_6_1() {
             shader.useProgram();
             let model: Matrix4<f32> = Matrix4::identity();
             let mut view = camera.GetViewMatrix();
-            let projection: Matrix4<f32> = perspective(Deg(camera.Zoom), SCR_WIDTH as f32 / SCR_HEIGHT as f32, 0.1, 100.0);
+            let projection: Matrix4<f32> = camera.GetProjectionMatrix(SCR_WIDTH as f32 / SCR_HEIGHT as f32);
             shader.setMat4(c_str!("model"), &model);
             shader.setMat4(c_str!("view"), &view);
             shader.setMat4(c_str!("projection"), &projection);
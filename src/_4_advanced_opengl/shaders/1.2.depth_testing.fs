@@ -3,14 +3,10 @@ out vec4 FragColor;
 
 float near = 0.1;
 float far = 100.0;
-float LinearizeDepth(float depth)
-{
-    float z = depth * 2.0 - 1.0; // back to NDC
-    return (2.0 * near * far) / (far + near - z * (far - near));
-}
+#include "common/depth.glsl"
 
 void main()
 {
-    float depth = LinearizeDepth(gl_FragCoord.z) / far; // divide by far to get depth in range [0,1] for visualization purposes
+    float depth = LinearizeDepth(gl_FragCoord.z, near, far) / far; // divide by far to get depth in range [0,1] for visualization purposes
     FragColor = vec4(vec3(depth), 1.0);
 }
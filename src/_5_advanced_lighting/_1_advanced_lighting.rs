@@ -3,7 +3,7 @@
 
 extern crate glfw;
 
-use self::glfw::{Context, Key, Action};
+use context::{self, Window};
 
 extern crate gl;
 
@@ -14,12 +14,12 @@ use std::mem;
 use std::os::raw::c_void;
 use std::ffi::CStr;
 
-use common::{process_events, loadTexture};
+use common::{process_events, processCameraInput, loadTexture};
+use input;
 use shader::Shader;
 use camera::Camera;
-use camera::Camera_Movement::*;
 
-use cgmath::{Matrix4, vec3, Deg, perspective, Point3};
+use cgmath::{Matrix4, vec3, Point3};
 use cgmath::prelude::*;
 
 // settings
@@ -45,7 +45,7 @@ pub fn main_5_1() {
 
     // glfw: initialize and configure
     // ------------------------------
-    let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
+    let mut glfw = context::init();
     glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
     glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
     #[cfg(target_os = "macos")]
@@ -152,7 +152,7 @@ pub fn main_5_1() {
 
             // draw objects
             shader.useProgram();
-            let projection: Matrix4<f32> = perspective(Deg(camera.Zoom), SCR_WIDTH as f32 / SCR_HEIGHT as f32, 0.1, 100.0);
+            let projection: Matrix4<f32> = camera.GetProjectionMatrix(SCR_WIDTH as f32 / SCR_HEIGHT as f32);
             let view = camera.GetViewMatrix();
             shader.setMat4(c_str!("projection"), &projection);
             shader.setMat4(c_str!("view"), &view);
@@ -182,30 +182,15 @@ pub fn main_5_1() {
 }
 
 // NOTE: not the same version as in common.rs
-pub fn processInput(window: &mut glfw::Window, deltaTime: f32, camera: &mut Camera, blinn: &mut bool, blinnKeyPressed: &mut bool) {
-    if window.get_key(Key::Escape) == Action::Press {
-        window.set_should_close(true)
-    }
-
-    if window.get_key(Key::W) == Action::Press {
-        camera.ProcessKeyboard(FORWARD, deltaTime);
-    }
-    if window.get_key(Key::S) == Action::Press {
-        camera.ProcessKeyboard(BACKWARD, deltaTime);
-    }
-    if window.get_key(Key::A) == Action::Press {
-        camera.ProcessKeyboard(LEFT, deltaTime);
-    }
-    if window.get_key(Key::D) == Action::Press {
-        camera.ProcessKeyboard(RIGHT, deltaTime);
-    }
+pub fn processInput(window: &mut Window, deltaTime: f32, camera: &mut Camera, blinn: &mut bool, blinnKeyPressed: &mut bool) {
+    processCameraInput(window, deltaTime, camera);
 
-    if window.get_key(Key::B) == Action::Press && !(*blinnKeyPressed) {
+    if input::pressed(window, "toggle_blinn") && !(*blinnKeyPressed) {
         *blinn = !(*blinn);
         *blinnKeyPressed = true;
         println!("{}", if *blinn { "Blinn-Phong" } else { "Phong" })
     }
-    if window.get_key(Key::B) == Action::Release {
+    if !input::pressed(window, "toggle_blinn") {
         *blinnKeyPressed = false;
     }
 }
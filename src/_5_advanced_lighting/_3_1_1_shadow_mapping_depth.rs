@@ -3,7 +3,7 @@
 
 extern crate glfw;
 
-use self::glfw::{Context, Key, Action};
+use context::{self, Window};
 
 extern crate gl;
 
@@ -20,10 +20,9 @@ use image::GenericImage;
 use image::DynamicImage::*;
 use image::GenericImageView;
 
-use common::{process_events, loadTexture};
+use common::{process_events, processCameraInput, loadTexture};
 use shader::Shader;
 use camera::Camera;
-use camera::Camera_Movement::*;
 
 use cgmath::{Matrix4, vec3, Vector3, Deg, perspective, ortho, Point3};
 use cgmath::prelude::*;
@@ -50,7 +49,7 @@ pub fn main_5_3_1_1() {
 
     // glfw: initialize and configure
     // ------------------------------
-    let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
+    let mut glfw = context::init();
     glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
     glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
     #[cfg(target_os = "macos")]
@@ -219,23 +218,8 @@ pub fn main_5_3_1_1() {
 }
 
 // NOTE: not the same version as in common.rs
-pub fn processInput(window: &mut glfw::Window, deltaTime: f32, camera: &mut Camera) {
-    if window.get_key(Key::Escape) == Action::Press {
-        window.set_should_close(true)
-    }
-
-    if window.get_key(Key::W) == Action::Press {
-        camera.ProcessKeyboard(FORWARD, deltaTime);
-    }
-    if window.get_key(Key::S) == Action::Press {
-        camera.ProcessKeyboard(BACKWARD, deltaTime);
-    }
-    if window.get_key(Key::A) == Action::Press {
-        camera.ProcessKeyboard(LEFT, deltaTime);
-    }
-    if window.get_key(Key::D) == Action::Press {
-        camera.ProcessKeyboard(RIGHT, deltaTime);
-    }
+pub fn processInput(window: &mut Window, deltaTime: f32, camera: &mut Camera) {
+    processCameraInput(window, deltaTime, camera);
 }
 
 unsafe fn renderScene(shader: &Shader, planeVAO: u32, cubeVAO: u32) {
@@ -3,7 +3,7 @@
 
 extern crate glfw;
 
-use self::glfw::{Context, Key, Action};
+use context::{self, Window};
 
 extern crate gl;
 
@@ -20,12 +20,11 @@ use image::GenericImage;
 use image::DynamicImage::*;
 use image::GenericImageView;
 
-use common::{process_events, loadTexture};
+use common::{process_events, processCameraInput, loadTexture};
 use shader::Shader;
 use camera::Camera;
-use camera::Camera_Movement::*;
 
-use cgmath::{Matrix4, vec3, Vector3, Deg, perspective, ortho, Point3};
+use cgmath::{Matrix4, vec3, Vector3, Deg, ortho, Point3};
 use cgmath::prelude::*;
 
 // settings
@@ -50,7 +49,7 @@ pub fn main_5_3_1_2() {
 
     // glfw: initialize and configure
     // ------------------------------
-    let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
+    let mut glfw = context::init();
     glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
     glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
     #[cfg(target_os = "macos")]
@@ -200,7 +199,7 @@ pub fn main_5_3_1_2() {
             gl::Viewport(0, 0, SCR_WIDTH as i32, SCR_HEIGHT as i32);
             gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
             shader.useProgram();
-            let projection = perspective(Deg(camera.Zoom), SCR_WIDTH as f32 / SCR_HEIGHT as f32, 0.1, 100.0);
+            let projection = camera.GetProjectionMatrix(SCR_WIDTH as f32 / SCR_HEIGHT as f32);
             let view = camera.GetViewMatrix();
             shader.setMat4(c_str!("projection"), &projection);
             shader.setMat4(c_str!("view"), &view);
@@ -240,23 +239,8 @@ pub fn main_5_3_1_2() {
 }
 
 // NOTE: not the same version as in common.rs
-pub fn processInput(window: &mut glfw::Window, deltaTime: f32, camera: &mut Camera) {
-    if window.get_key(Key::Escape) == Action::Press {
-        window.set_should_close(true)
-    }
-
-    if window.get_key(Key::W) == Action::Press {
-        camera.ProcessKeyboard(FORWARD, deltaTime);
-    }
-    if window.get_key(Key::S) == Action::Press {
-        camera.ProcessKeyboard(BACKWARD, deltaTime);
-    }
-    if window.get_key(Key::A) == Action::Press {
-        camera.ProcessKeyboard(LEFT, deltaTime);
-    }
-    if window.get_key(Key::D) == Action::Press {
-        camera.ProcessKeyboard(RIGHT, deltaTime);
-    }
+pub fn processInput(window: &mut Window, deltaTime: f32, camera: &mut Camera) {
+    processCameraInput(window, deltaTime, camera);
 }
 
 unsafe fn renderScene(shader: &Shader, planeVAO: u32, cubeVAO: u32) {
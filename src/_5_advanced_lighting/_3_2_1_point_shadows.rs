@@ -3,7 +3,7 @@
 
 extern crate glfw;
 
-use self::glfw::{Context, Key, Action};
+use context::{self, Window};
 
 extern crate gl;
 
@@ -21,14 +21,13 @@ use image::GenericImage;
 use image::DynamicImage::*;
 use image::GenericImageView;
 
-use common::{process_events, loadTexture};
+use common::{process_events, processCameraInput, loadTexture};
+use input;
 use shader::Shader;
 use camera::Camera;
-use camera::Camera_Movement::*;
 
 use cgmath::{Matrix4, vec3, Vector3, Deg, perspective, ortho, Point3};
 use cgmath::prelude::*;
-use self::glfw::ffi::glfwGetTime;
 
 // settings
 const SCR_WIDTH: u32 = 1280;
@@ -52,7 +51,7 @@ pub fn main_5_3_2_1() {
 
     // glfw: initialize and configure
     // ------------------------------
-    let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
+    let mut glfw = context::init();
     glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
     glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
     #[cfg(target_os = "macos")]
@@ -87,9 +86,11 @@ pub fn main_5_3_2_1() {
             "src/_5_advanced_lighting/shaders/3.2.1.point_shadows.vs",
             "src/_5_advanced_lighting/shaders/3.2.1.point_shadows.fs",
         );
-        let simpleDepthShader = Shader::new(
+        let simpleDepthShader = Shader::with_geometry_shader(
             "src/_5_advanced_lighting/shaders/3.2.1.point_shadows_depth.vs",
-            "src/_5_advanced_lighting/shaders/3.2.1.point_shadows_depth.fs");
+            "src/_5_advanced_lighting/shaders/3.2.1.point_shadows_depth.fs",
+            "src/_5_advanced_lighting/shaders/3.2.1.point_shadows_depth.gs",
+        );
 
         // load textures
         // -------------
@@ -148,7 +149,7 @@ pub fn main_5_3_2_1() {
         // render
         // ------
         unsafe {
-            lightPos.z = ((glfwGetTime() * 0.5).sin() * 3.0) as f32;
+            lightPos.z = ((glfw.get_time() * 0.5).sin() * 3.0) as f32;
 
             gl::ClearColor(0.1, 0.1, 0.1, 1.0);
             gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
@@ -180,7 +181,7 @@ pub fn main_5_3_2_1() {
             gl::Viewport(0, 0, SCR_WIDTH as i32, SCR_HEIGHT as i32);
             gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
             shader.useProgram();
-            let projection = perspective(Deg(camera.Zoom), SCR_WIDTH as f32 / SCR_HEIGHT as f32, 0.1, 100.0);
+            let projection = camera.GetProjectionMatrix(SCR_WIDTH as f32 / SCR_HEIGHT as f32);
             let view = camera.GetViewMatrix();
             shader.setMat4(c_str!("projection"), &projection);
             shader.setMat4(c_str!("view"), &view);
@@ -210,30 +211,15 @@ pub fn main_5_3_2_1() {
 }
 
 // NOTE: not the same version as in common.rs
-pub fn processInput(window: &mut glfw::Window, deltaTime: f32, camera: &mut Camera, shadowsKeyPressed: &mut bool, shadows: &mut bool) {
-    if window.get_key(Key::Escape) == Action::Press {
-        window.set_should_close(true)
-    }
-
-    if window.get_key(Key::W) == Action::Press {
-        camera.ProcessKeyboard(FORWARD, deltaTime);
-    }
-    if window.get_key(Key::S) == Action::Press {
-        camera.ProcessKeyboard(BACKWARD, deltaTime);
-    }
-    if window.get_key(Key::A) == Action::Press {
-        camera.ProcessKeyboard(LEFT, deltaTime);
-    }
-    if window.get_key(Key::D) == Action::Press {
-        camera.ProcessKeyboard(RIGHT, deltaTime);
-    }
+pub fn processInput(window: &mut Window, deltaTime: f32, camera: &mut Camera, shadowsKeyPressed: &mut bool, shadows: &mut bool) {
+    processCameraInput(window, deltaTime, camera);
 
-    if window.get_key(Key::Space) == Action::Press && !*shadowsKeyPressed {
+    if input::pressed(window, "toggle_shadows") && !*shadowsKeyPressed {
         *shadows = !*shadows;
         *shadowsKeyPressed = true;
     }
 
-    if window.get_key(Key::Space) == Action::Release {
+    if !input::pressed(window, "toggle_shadows") {
         *shadowsKeyPressed = false;
     }
 }
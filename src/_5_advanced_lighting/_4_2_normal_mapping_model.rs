@@ -0,0 +1,146 @@
+#![allow(non_upper_case_globals)]
+#![allow(non_snake_case)]
+
+extern crate glfw;
+
+use context::{self, Window};
+
+extern crate gl;
+
+use common::{process_events, processCameraInput};
+use input;
+use shader::Shader;
+use camera::Camera;
+use model::Model;
+
+use cgmath::{Matrix4, vec3, Vector3, Point3};
+
+// settings
+const SCR_WIDTH: u32 = 1280;
+const SCR_HEIGHT: u32 = 720;
+
+/// Normal mapping on a loaded model: the tangents and bitangents of the cyborg are calculated
+/// from its texture coordinates while loading (see `mesh::calculateTangents`). The light circles
+/// around the model; press space to compare with the plain vertex normals.
+pub fn main_5_4_2() {
+    let mut normalMapping = true;
+    let mut normalMappingKeyPressed = false;
+
+    let mut camera = Camera {
+        Position: Point3::new(0.0, 0.0, 5.0),
+        ..Camera::default()
+    };
+
+    let mut firstMouse = true;
+    let mut lastX: f32 = SCR_WIDTH as f32 / 2.0;
+    let mut lastY: f32 = SCR_HEIGHT as f32 / 2.0;
+
+    // timing
+    let mut deltaTime: f32; // time between current frame and last frame
+    let mut lastFrame: f32 = 0.0;
+
+    // glfw: initialize and configure
+    // ------------------------------
+    let mut glfw = context::init();
+    glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
+    glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
+    #[cfg(target_os = "macos")]
+        glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
+
+    // glfw window creation
+    // --------------------
+    let (mut window, events) = glfw.create_window(SCR_WIDTH, SCR_HEIGHT, "LearnOpenGL", glfw::WindowMode::Windowed)
+        .expect("Failed to create GLFW window");
+
+    window.make_current();
+    window.set_framebuffer_size_polling(true);
+    window.set_cursor_pos_polling(true);
+    window.set_scroll_polling(true);
+
+    // tell GLFW to capture our mouse
+    window.set_cursor_mode(glfw::CursorMode::Disabled);
+
+    // gl: load all OpenGL function pointers
+    // ---------------------------------------
+    gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+
+    let (shader, cyborg) = unsafe {
+        // configure global opengl state
+        // -----------------------------
+        gl::Enable(gl::DEPTH_TEST);
+
+        // build and compile shaders
+        // ------------------------------------
+        let shader = Shader::new(
+            "src/_5_advanced_lighting/shaders/4.2.normal_mapping_model.vs",
+            "src/_5_advanced_lighting/shaders/4.2.normal_mapping_model.fs");
+
+        // load models
+        // -----------
+        let cyborg = Model::new("resources/objects/cyborg/cyborg.obj");
+
+        (shader, cyborg)
+    };
+
+    // render loop
+    // -----------
+    while !window.should_close() {
+        // per-frame time logic
+        // --------------------
+        let currentFrame = glfw.get_time() as f32;
+        deltaTime = currentFrame - lastFrame;
+        lastFrame = currentFrame;
+
+        // events
+        // -----
+        process_events(&events, &mut firstMouse, &mut lastX, &mut lastY, &mut camera);
+
+        // input
+        // -----
+        processInput(&mut window, deltaTime, &mut camera, &mut normalMapping, &mut normalMappingKeyPressed);
+
+        // render
+        // ------
+        unsafe {
+            gl::ClearColor(0.1, 0.1, 0.1, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+            // lighting info: move the light around the model so the details of the normal map stand out
+            let lightPos: Vector3<f32> = vec3(currentFrame.sin() * 2.0, 1.0, currentFrame.cos() * 2.0 + 1.0);
+
+            // configure view/projection matrices
+            let projection: Matrix4<f32> = camera.GetProjectionMatrix(SCR_WIDTH as f32 / SCR_HEIGHT as f32);
+            let view = camera.GetViewMatrix();
+            shader.useProgram();
+            shader.set("projection", projection);
+            shader.set("view", view);
+            shader.set("viewPos", camera.Position);
+            shader.set("lightPos", lightPos);
+            shader.set("normalMapping", normalMapping);
+
+            // render the normal-mapped model
+            let model = Matrix4::from_translation(vec3(0.0, -1.85, 0.0)); // translate it down so it's at the center of the scene
+            shader.set("model", model);
+            cyborg.Draw(&shader);
+        }
+
+        // glfw: swap buffers and poll IO events (keys pressed/released, mouse moved etc.)
+        // -------------------------------------------------------------------------------
+        window.swap_buffers();
+        glfw.poll_events();
+    }
+}
+
+// NOTE: not the same version as in common.rs
+pub fn processInput(window: &mut Window, deltaTime: f32, camera: &mut Camera, normalMapping: &mut bool, normalMappingKeyPressed: &mut bool) {
+    processCameraInput(window, deltaTime, camera);
+
+    if input::pressed(window, "toggle_normal_mapping") && !(*normalMappingKeyPressed) {
+        *normalMapping = !(*normalMapping);
+        *normalMappingKeyPressed = true;
+        println!("normal mapping: {}", if *normalMapping { "on" } else { "off" })
+    }
+    if !input::pressed(window, "toggle_normal_mapping") {
+        *normalMappingKeyPressed = false;
+    }
+}
@@ -3,7 +3,7 @@
 
 extern crate glfw;
 
-use self::glfw::{Context, Key, Action};
+use context;
 
 extern crate gl;
 
@@ -15,10 +15,11 @@ use std::os::raw::c_void;
 use std::ffi::CStr;
 
 use common::{process_events, processInput, loadTexture};
+use input;
 use shader::Shader;
 use camera::Camera;
 
-use cgmath::{Matrix4, vec3, Vector3, vec2, Vector2, Deg, perspective, Point3, Rad};
+use cgmath::{Matrix4, vec3, Vector3, vec2, Vector2, Deg, Point3, Rad};
 use cgmath::prelude::*;
 
 // settings
@@ -42,7 +43,7 @@ pub fn main_5_5_2() {
 
     // glfw: initialize and configure
     // ------------------------------
-    let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
+    let mut glfw = context::init();
     glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
     glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
     #[cfg(target_os = "macos")]
@@ -115,13 +116,13 @@ pub fn main_5_5_2() {
         // input
         // -----
         processInput(&mut window, deltaTime, &mut camera);
-        if window.get_key(Key::Q) == Action::Press {
+        if input::pressed(&window, "decrease") {
             if heightScale > 0.0 {
                 heightScale -= 0.0005;
             } else {
                 heightScale = 0.0;
             }
-        } else if window.get_key(Key::E) == Action::Press {
+        } else if input::pressed(&window, "increase") {
             if heightScale < 1.0 {
                 heightScale += 0.0005;
             } else {
@@ -136,7 +137,7 @@ pub fn main_5_5_2() {
             gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
 
             // configure view/projection matrices
-            let projection: Matrix4<f32> = perspective(Deg(camera.Zoom), SCR_WIDTH as f32 / SCR_HEIGHT as f32, 0.1, 100.0);
+            let projection: Matrix4<f32> = camera.GetProjectionMatrix(SCR_WIDTH as f32 / SCR_HEIGHT as f32);
             let view = camera.GetViewMatrix();
             shader.useProgram();
             shader.setMat4(c_str!("projection"), &projection);
@@ -3,7 +3,7 @@
 
 extern crate glfw;
 
-use self::glfw::{Context, Key, Action};
+use context::{self, Window};
 
 extern crate gl;
 
@@ -20,12 +20,13 @@ use image::GenericImage;
 use image::DynamicImage::*;
 use image::GenericImageView;
 
-use common::process_events;
+use common::{process_events, processCameraInput};
+use input;
 use shader::Shader;
+use capture;
 use camera::Camera;
-use camera::Camera_Movement::*;
 
-use cgmath::{Matrix4, vec3, Vector3, Deg, perspective, Point3};
+use cgmath::{Matrix4, vec3, Vector3, Deg, Point3};
 use cgmath::prelude::*;
 
 // settings
@@ -52,7 +53,7 @@ pub fn main_5_7() {
 
     // glfw: initialize and configure
     // ------------------------------
-    let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
+    let mut glfw = context::init();
     glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
     glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
     #[cfg(target_os = "macos")]
@@ -156,6 +157,11 @@ pub fn main_5_7() {
                 println!("Framebuffer not complete!");
             }
         }
+        // make the floating point buffers available to `--capture-fbo <name>`; with an even
+        // number of blur passes the final result ends up in the first ping-pong buffer
+        capture::register_framebuffer("scene", hdrFBO, gl::COLOR_ATTACHMENT0);
+        capture::register_framebuffer("bright", hdrFBO, gl::COLOR_ATTACHMENT1);
+        capture::register_framebuffer("blur", pingpongFBO[0], gl::COLOR_ATTACHMENT0);
 
         // lighting info
         // -------------
@@ -215,7 +221,7 @@ pub fn main_5_7() {
             // -----------------------------------------------
             gl::BindFramebuffer(gl::FRAMEBUFFER, hdrFBO);
             gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
-            let projection: Matrix4<f32> = perspective(Deg(camera.Zoom), SCR_WIDTH as f32 / SCR_HEIGHT as f32, 0.1, 100.0);
+            let projection: Matrix4<f32> = camera.GetProjectionMatrix(SCR_WIDTH as f32 / SCR_HEIGHT as f32);
             let view = camera.GetViewMatrix();
             shader.useProgram();
             shader.setMat4(c_str!("projection"), &projection);
@@ -431,36 +437,21 @@ unsafe fn renderQuad(quadVAO: &mut u32, quadVBO: &mut u32) {
 
 // NOTE: not the same version as in common.rs
 pub fn processInput(
-    window: &mut glfw::Window, deltaTime: f32, camera: &mut Camera,
+    window: &mut Window, deltaTime: f32, camera: &mut Camera,
     bloom: &mut bool, bloomKeyPressed: &mut bool, exposure: &mut f32)
 {
-    if window.get_key(Key::Escape) == Action::Press {
-        window.set_should_close(true)
-    }
-
-    if window.get_key(Key::W) == Action::Press {
-        camera.ProcessKeyboard(FORWARD, deltaTime);
-    }
-    if window.get_key(Key::S) == Action::Press {
-        camera.ProcessKeyboard(BACKWARD, deltaTime);
-    }
-    if window.get_key(Key::A) == Action::Press {
-        camera.ProcessKeyboard(LEFT, deltaTime);
-    }
-    if window.get_key(Key::D) == Action::Press {
-        camera.ProcessKeyboard(RIGHT, deltaTime);
-    }
+    processCameraInput(window, deltaTime, camera);
 
-    if window.get_key(Key::Space) == Action::Press && !(*bloomKeyPressed) {
+    if input::pressed(window, "toggle_bloom") && !(*bloomKeyPressed) {
         *bloom = !(*bloom);
         *bloomKeyPressed = true;
         println!("hdr: {} | exposure: {}", if *bloom { "on" } else { "off" }, *exposure);
     }
-    if window.get_key(Key::Space) == Action::Release {
+    if !input::pressed(window, "toggle_bloom") {
         *bloomKeyPressed = false;
     }
 
-    if window.get_key(Key::Q) == Action::Press {
+    if input::pressed(window, "decrease") {
         if *exposure > 0.0 {
             *exposure -= 0.01;
         } else {
@@ -468,7 +459,7 @@ pub fn processInput(
         }
         println!("hdr: {} | exposure: {}", if *bloom { "on" } else { "off" }, *exposure);
     }
-    if window.get_key(Key::E) == Action::Press {
+    if input::pressed(window, "increase") {
         *exposure += 0.01;
         println!("hdr: {} | exposure: {}", if *bloom { "on" } else { "off" }, *exposure);
     }
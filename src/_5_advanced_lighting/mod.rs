@@ -34,6 +34,10 @@ mod _4_normal_mapping;
 
 pub use self::_4_normal_mapping::*;
 
+mod _4_2_normal_mapping_model;
+
+pub use self::_4_2_normal_mapping_model::*;
+
 mod _5_1_parallax_mapping;
 
 pub use self::_5_1_parallax_mapping::*;
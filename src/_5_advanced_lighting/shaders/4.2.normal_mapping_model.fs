@@ -0,0 +1,44 @@
+#version 330 core
+out vec4 FragColor;
+
+in VS_OUT {
+    vec3 FragPos;
+    vec2 TexCoords;
+    vec3 TangentLightPos;
+    vec3 TangentViewPos;
+    vec3 TangentFragPos;
+} fs_in;
+
+uniform sampler2D texture_diffuse1;
+uniform sampler2D texture_specular1;
+uniform sampler2D texture_normal1;
+
+uniform bool normalMapping;
+
+void main()
+{
+    vec3 normal = vec3(0.0, 0.0, 1.0);
+    if(normalMapping)
+    {
+        // obtain normal from normal map in range [0,1]
+        normal = texture(texture_normal1, fs_in.TexCoords).rgb;
+        // transform normal vector to range [-1,1]
+        normal = normalize(normal * 2.0 - 1.0);  // this normal is in tangent space
+    }
+
+    // get diffuse color
+    vec3 color = texture(texture_diffuse1, fs_in.TexCoords).rgb;
+    // ambient
+    vec3 ambient = 0.1 * color;
+    // diffuse
+    vec3 lightDir = normalize(fs_in.TangentLightPos - fs_in.TangentFragPos);
+    float diff = max(dot(lightDir, normal), 0.0);
+    vec3 diffuse = diff * color;
+    // specular
+    vec3 viewDir = normalize(fs_in.TangentViewPos - fs_in.TangentFragPos);
+    vec3 halfwayDir = normalize(lightDir + viewDir);
+    float spec = pow(max(dot(normal, halfwayDir), 0.0), 32.0);
+    vec3 specular = texture(texture_specular1, fs_in.TexCoords).rgb * spec;
+
+    FragColor = vec4(ambient + diffuse + specular, 1.0);
+}
@@ -3,7 +3,7 @@
 
 extern crate glfw;
 
-use self::glfw::Context;
+use context;
 
 extern crate gl;
 
@@ -19,7 +19,7 @@ use common::{process_events, processInput, loadTexture};
 use shader::Shader;
 use camera::Camera;
 
-use cgmath::{Matrix4, vec3, Vector3, vec2, Deg, perspective, Point3};
+use cgmath::{Matrix4, vec3, Vector3, vec2, Point3};
 use cgmath::prelude::*;
 
 // settings
@@ -42,7 +42,7 @@ pub fn main_6_1_2() {
 
     // glfw: initialize and configure
     // ------------------------------
-    let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
+    let mut glfw = context::init();
     glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
     glfw.window_hint(glfw::WindowHint::Samples(Some(4)));
     glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
@@ -95,7 +95,7 @@ pub fn main_6_1_2() {
 
         // initialize static shader uniforms before rendering
         // --------------------------------------------------
-        let projection: Matrix4<f32> = perspective(Deg(camera.Zoom), SCR_WIDTH as f32 / SCR_HEIGHT as f32, 0.1, 100.0);
+        let projection: Matrix4<f32> = camera.GetProjectionMatrix(SCR_WIDTH as f32 / SCR_HEIGHT as f32);
         shader.setMat4(c_str!("projection"), &projection);
 
         (shader, albedo, normal, metallic, roughness, ao)
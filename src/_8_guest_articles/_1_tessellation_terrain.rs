@@ -0,0 +1,276 @@
+#![allow(non_upper_case_globals)]
+#![allow(non_snake_case)]
+
+extern crate glfw;
+
+use context::{self, Window};
+
+extern crate gl;
+
+use self::gl::types::*;
+
+use std::ptr;
+use std::mem;
+use std::os::raw::c_void;
+
+use common::{process_events, processCameraInput};
+use input;
+use shader::ShaderBuilder;
+use camera::{Camera, Projection};
+
+use cgmath::{Matrix4, Point3};
+use cgmath::prelude::*;
+
+// settings
+const SCR_WIDTH: u32 = 1280;
+const SCR_HEIGHT: u32 = 720;
+
+// terrain
+const TERRAIN_SIZE: f32 = 1024.0;
+const NUM_PATCHES: u32 = 20;
+const HEIGHTMAP_SIZE: usize = 512;
+
+/// Renders a heightmap as a grid of quad patches; the tessellation control shader picks the
+/// subdivision level of each patch edge from its distance to the camera and the evaluation shader
+/// displaces the generated vertices with the heightmap. Press space to toggle wireframe mode.
+pub fn main_8_1() {
+    let mut wireframe = false;
+    let mut wireframeKeyPressed = false;
+
+    let mut camera = Camera {
+        Position: Point3::new(0.0, 100.0, 600.0),
+        MovementSpeed: 100.0,
+        // the terrain is much larger than the default far plane
+        Projection: Projection::Perspective { near: 0.1, far: 2000.0 },
+        ..Camera::default()
+    };
+    // look down at the terrain a bit
+    camera.ProcessMouseMovement(0.0, -25.0 / camera.MouseSensitivity, true);
+
+    let mut firstMouse = true;
+    let mut lastX: f32 = SCR_WIDTH as f32 / 2.0;
+    let mut lastY: f32 = SCR_HEIGHT as f32 / 2.0;
+
+    // timing
+    let mut deltaTime: f32; // time between current frame and last frame
+    let mut lastFrame: f32 = 0.0;
+
+    // glfw: initialize and configure
+    // ------------------------------
+    let mut glfw = context::init();
+    // tessellation shaders require OpenGL 4.0, 4.1 is the highest version available on macOS
+    glfw.window_hint(glfw::WindowHint::ContextVersion(4, 1));
+    glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
+    #[cfg(target_os = "macos")]
+        glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
+
+    // glfw window creation
+    // --------------------
+    let (mut window, events) = glfw.create_window(SCR_WIDTH, SCR_HEIGHT, "LearnOpenGL", glfw::WindowMode::Windowed)
+        .expect("Failed to create GLFW window");
+
+    window.make_current();
+    window.set_framebuffer_size_polling(true);
+    window.set_cursor_pos_polling(true);
+    window.set_scroll_polling(true);
+
+    // tell GLFW to capture our mouse
+    window.set_cursor_mode(glfw::CursorMode::Disabled);
+
+    // gl: load all OpenGL function pointers
+    // ---------------------------------------
+    gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+
+    let (shader, terrainVBO, terrainVAO, heightMap) = unsafe {
+        // configure global opengl state
+        // -----------------------------
+        gl::Enable(gl::DEPTH_TEST);
+
+        let mut maxTessLevel = 0;
+        gl::GetIntegerv(gl::MAX_TESS_GEN_LEVEL, &mut maxTessLevel);
+        println!("Max available tess level: {}", maxTessLevel);
+
+        // build and compile our shader program
+        // ------------------------------------
+        let shader = ShaderBuilder::new()
+            .vertex("src/_8_guest_articles/shaders/1.terrain.vs")
+            .tess_control("src/_8_guest_articles/shaders/1.terrain.tcs")
+            .tess_evaluation("src/_8_guest_articles/shaders/1.terrain.tes")
+            .fragment("src/_8_guest_articles/shaders/1.terrain.fs")
+            .build()
+            .unwrap_or_else(|e| panic!("{}", e));
+
+        // load the heightmap
+        // ------------------
+        let heightMap = createHeightMap(HEIGHTMAP_SIZE);
+
+        // set up vertex data (and buffer(s)) and configure vertex attributes
+        // ------------------------------------------------------------------
+        // one patch of 4 vertices (position + texture coordinates) per grid cell
+        let mut vertices: Vec<f32> = Vec::with_capacity((NUM_PATCHES * NUM_PATCHES * 4 * 5) as usize);
+        let n = NUM_PATCHES as f32;
+        for i in 0..NUM_PATCHES {
+            for j in 0..NUM_PATCHES {
+                let (i, j) = (i as f32, j as f32);
+                for &(di, dj) in &[(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)] {
+                    vertices.extend_from_slice(&[
+                        -TERRAIN_SIZE / 2.0 + TERRAIN_SIZE * (i + di) / n, // x
+                        0.0,                                               // y
+                        -TERRAIN_SIZE / 2.0 + TERRAIN_SIZE * (j + dj) / n, // z
+                        (i + di) / n,                                      // u
+                        (j + dj) / n,                                      // v
+                    ]);
+                }
+            }
+        }
+
+        let (mut terrainVAO, mut terrainVBO) = (0, 0);
+        gl::GenVertexArrays(1, &mut terrainVAO);
+        gl::GenBuffers(1, &mut terrainVBO);
+        gl::BindVertexArray(terrainVAO);
+        gl::BindBuffer(gl::ARRAY_BUFFER, terrainVBO);
+        gl::BufferData(gl::ARRAY_BUFFER,
+                       (vertices.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
+                       vertices.as_ptr() as *const c_void,
+                       gl::STATIC_DRAW);
+        let stride = 5 * mem::size_of::<GLfloat>() as GLsizei;
+        // position attribute
+        gl::EnableVertexAttribArray(0);
+        gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, stride, ptr::null());
+        // texCoord attribute
+        gl::EnableVertexAttribArray(1);
+        gl::VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE, stride, (3 * mem::size_of::<GLfloat>()) as *const c_void);
+        gl::BindVertexArray(0);
+
+        gl::PatchParameteri(gl::PATCH_VERTICES, 4);
+
+        // shader configuration
+        // --------------------
+        shader.useProgram();
+        shader.set("heightMap", 0);
+        shader.set("heightScale", 64.0f32);
+        shader.set("heightShift", 16.0f32);
+
+        (shader, terrainVBO, terrainVAO, heightMap)
+    };
+
+    // render loop
+    // -----------
+    while !window.should_close() {
+        // per-frame time logic
+        // --------------------
+        let currentFrame = glfw.get_time() as f32;
+        deltaTime = currentFrame - lastFrame;
+        lastFrame = currentFrame;
+
+        // events
+        // -----
+        process_events(&events, &mut firstMouse, &mut lastX, &mut lastY, &mut camera);
+
+        // input
+        // -----
+        processInput(&mut window, deltaTime, &mut camera, &mut wireframe, &mut wireframeKeyPressed);
+
+        // render
+        // ------
+        unsafe {
+            gl::ClearColor(0.1, 0.1, 0.1, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+            gl::PolygonMode(gl::FRONT_AND_BACK, if wireframe { gl::LINE } else { gl::FILL });
+
+            shader.useProgram();
+            let projection: Matrix4<f32> = camera.GetProjectionMatrix(SCR_WIDTH as f32 / SCR_HEIGHT as f32);
+            let view = camera.GetViewMatrix();
+            shader.set("projection", projection);
+            shader.set("view", view);
+            shader.set("model", Matrix4::<f32>::identity());
+
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, heightMap);
+            gl::BindVertexArray(terrainVAO);
+            gl::DrawArrays(gl::PATCHES, 0, (4 * NUM_PATCHES * NUM_PATCHES) as GLsizei);
+            gl::BindVertexArray(0);
+            gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
+        }
+
+        // glfw: swap buffers and poll IO events (keys pressed/released, mouse moved etc.)
+        // -------------------------------------------------------------------------------
+        window.swap_buffers();
+        glfw.poll_events();
+    }
+
+    // optional: de-allocate all resources once they've outlived their purpose:
+    // ------------------------------------------------------------------------
+    unsafe {
+        gl::DeleteVertexArrays(1, &terrainVAO);
+        gl::DeleteBuffers(1, &terrainVBO);
+        gl::DeleteTextures(1, &heightMap);
+    }
+}
+
+// NOTE: not the same version as in common.rs
+pub fn processInput(window: &mut Window, deltaTime: f32, camera: &mut Camera, wireframe: &mut bool, wireframeKeyPressed: &mut bool) {
+    processCameraInput(window, deltaTime, camera);
+
+    if input::pressed(window, "toggle_wireframe") && !(*wireframeKeyPressed) {
+        *wireframe = !(*wireframe);
+        *wireframeKeyPressed = true;
+    }
+    if !input::pressed(window, "toggle_wireframe") {
+        *wireframeKeyPressed = false;
+    }
+}
+
+/// Generates a heightmap with values in [0, 1] from a few octaves of value noise and uploads it
+/// as a single channel float texture (the article loads a DEM image instead)
+unsafe fn createHeightMap(size: usize) -> u32 {
+    let mut data = vec![0f32; size * size];
+    for y in 0..size {
+        for x in 0..size {
+            let (mut frequency, mut amplitude, mut height, mut total) = (4.0 / size as f32, 1.0, 0.0, 0.0);
+            for octave in 0..6 {
+                height += amplitude * valueNoise(x as f32 * frequency, y as f32 * frequency, octave);
+                total += amplitude;
+                frequency *= 2.0;
+                amplitude *= 0.5;
+            }
+            data[y * size + x] = height / total;
+        }
+    }
+
+    let mut textureID = 0;
+    gl::GenTextures(1, &mut textureID);
+    gl::BindTexture(gl::TEXTURE_2D, textureID);
+    gl::TexImage2D(gl::TEXTURE_2D, 0, gl::R32F as i32, size as i32, size as i32, 0, gl::RED, gl::FLOAT,
+                   data.as_ptr() as *const c_void);
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+    textureID
+}
+
+/// Smoothly interpolated random values on an integer lattice, in [0, 1]
+fn valueNoise(x: f32, y: f32, seed: u32) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (tx, ty) = (smoothstep(x - x0), smoothstep(y - y0));
+    let (ix, iy) = (x0 as i32, y0 as i32);
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+    lerp(lerp(lattice(ix, iy, seed), lattice(ix + 1, iy, seed), tx),
+         lerp(lattice(ix, iy + 1, seed), lattice(ix + 1, iy + 1, seed), tx),
+         ty)
+}
+
+fn smoothstep(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Deterministic pseudo random value in [0, 1] for a lattice point
+fn lattice(x: i32, y: i32, seed: u32) -> f32 {
+    let mut h = (x as u32).wrapping_mul(374_761_393)
+        ^ (y as u32).wrapping_mul(668_265_263)
+        ^ seed.wrapping_mul(2_246_822_519);
+    h = (h ^ (h >> 13)).wrapping_mul(1_274_126_177);
+    h ^= h >> 16;
+    (h & 0xffff) as f32 / 65535.0
+}
@@ -0,0 +1,129 @@
+#![allow(non_upper_case_globals)]
+#![allow(non_snake_case)]
+extern crate glfw;
+
+use context;
+
+extern crate gl;
+
+use common::{process_events, processInput};
+use shader::Shader;
+use camera::{Camera, Projection};
+use model::Model;
+use animation::Animator;
+
+use cgmath::{Matrix4, Point3};
+use cgmath::prelude::*;
+
+// settings
+const SCR_WIDTH: u32 = 800;
+const SCR_HEIGHT: u32 = 600;
+
+/// Plays the first animation of a skinned glTF model. The article uses assimp and a Mixamo
+/// character; the walking figure here is a small generated glTF file with embedded buffers and
+/// texture, but any skinned glTF model with up to 100 bones works.
+pub fn main_8_2() {
+    let mut camera = Camera {
+        Position: Point3::new(0.0, 1.0, 3.0),
+        Projection: Projection::Perspective { near: 0.1, far: 100.0 },
+        ..Camera::default()
+    };
+
+    let mut firstMouse = true;
+    let mut lastX: f32 = SCR_WIDTH as f32 / 2.0;
+    let mut lastY: f32 = SCR_HEIGHT as f32 / 2.0;
+
+    // timing
+    let mut deltaTime: f32; // time between current frame and last frame
+    let mut lastFrame: f32 = 0.0;
+
+    // glfw: initialize and configure
+    // ------------------------------
+    let mut glfw = context::init();
+    glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
+    glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
+    #[cfg(target_os = "macos")]
+        glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
+
+    // glfw window creation
+    // --------------------
+    let (mut window, events) = glfw.create_window(SCR_WIDTH, SCR_HEIGHT, "LearnOpenGL", glfw::WindowMode::Windowed)
+        .expect("Failed to create GLFW window");
+
+    window.make_current();
+    window.set_framebuffer_size_polling(true);
+    window.set_cursor_pos_polling(true);
+    window.set_scroll_polling(true);
+
+    // tell GLFW to capture our mouse
+    window.set_cursor_mode(glfw::CursorMode::Disabled);
+
+    // gl: load all OpenGL function pointers
+    // ---------------------------------------
+    gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+
+    let (ourShader, ourModel) = unsafe {
+        // configure global opengl state
+        // -----------------------------
+        gl::Enable(gl::DEPTH_TEST);
+
+        // build and compile shaders
+        // -------------------------
+        let ourShader = Shader::new(
+            "src/_8_guest_articles/shaders/2.anim_model.vs",
+            "src/_8_guest_articles/shaders/2.anim_model.fs");
+
+        // load models
+        // -----------
+        let ourModel = Model::new("resources/objects/walker/walker.gltf");
+
+        (ourShader, ourModel)
+    };
+    let mut animator = Animator::new(&ourModel);
+
+    // render loop
+    // -----------
+    while !window.should_close() {
+        // per-frame time logic
+        // --------------------
+        let currentFrame = glfw.get_time() as f32;
+        deltaTime = currentFrame - lastFrame;
+        lastFrame = currentFrame;
+
+        // events
+        // -----
+        process_events(&events, &mut firstMouse, &mut lastX, &mut lastY, &mut camera);
+
+        // input
+        // -----
+        processInput(&mut window, deltaTime, &mut camera);
+        animator.UpdateAnimation(deltaTime, &ourModel);
+
+        // render
+        // ------
+        unsafe {
+            gl::ClearColor(0.05, 0.05, 0.05, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+            // don't forget to enable shader before setting uniforms
+            ourShader.useProgram();
+
+            // view/projection transformations
+            let projection: Matrix4<f32> = camera.GetProjectionMatrix(SCR_WIDTH as f32 / SCR_HEIGHT as f32);
+            let view = camera.GetViewMatrix();
+            ourShader.set("projection", projection);
+            ourShader.set("view", view);
+
+            ourShader.set("finalBonesMatrices", animator.GetFinalBoneMatrices());
+
+            // render the loaded model
+            ourShader.set("model", Matrix4::<f32>::identity());
+            ourModel.Draw(&ourShader);
+        }
+
+        // glfw: swap buffers and poll IO events (keys pressed/released, mouse moved etc.)
+        // -------------------------------------------------------------------------------
+        window.swap_buffers();
+        glfw.poll_events();
+    }
+}
@@ -0,0 +1,5 @@
+mod _1_tessellation_terrain;
+mod _2_skeletal_animation;
+
+pub use self::_1_tessellation_terrain::*;
+pub use self::_2_skeletal_animation::*;
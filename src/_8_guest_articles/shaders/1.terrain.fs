@@ -0,0 +1,11 @@
+#version 410 core
+out vec4 FragColor;
+
+in float Height;
+
+void main()
+{
+    // height is in [0, 1]: dark valleys, bright peaks
+    float h = Height * 0.8 + 0.2;
+    FragColor = vec4(h, h, h, 1.0);
+}
@@ -0,0 +1,19 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+in vec3 Normal;
+
+uniform sampler2D texture_diffuse1;
+uniform vec4 baseColorFactor;
+
+void main()
+{
+    // a bit of directional light so the limbs can be told apart
+    float diffuse = max(dot(normalize(Normal), normalize(vec3(0.4, 1.0, 0.8))), 0.0);
+    // the base color texture is sRGB, so the color is sampled (and lit) in linear space
+    vec4 color = texture(texture_diffuse1, TexCoords) * baseColorFactor;
+    vec3 lit = color.rgb * (0.3 + 0.7 * diffuse);
+    // gamma correct
+    FragColor = vec4(pow(lit, vec3(1.0 / 2.2)), color.a);
+}
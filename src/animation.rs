@@ -0,0 +1,380 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+
+/// Skeletal animation as in https://learnopengl.com/Guest-Articles/2020/Skeletal-Animation, but
+/// loaded from glTF instead of through assimp. The `Skeleton` is the node hierarchy of the model
+/// together with the bones (the joints of its skins), an `Animation` is a clip of keyframes for the
+/// translation, rotation and scale of some of the nodes and the `Animator` samples the current clip
+/// every frame and computes the matrices that move the vertices from the bind pose into the
+/// animated pose. Each vertex is influenced by up to `MAX_BONE_INFLUENCE` bones (`Vertex::BoneIDs`
+/// and `Vertex::Weights`).
+
+use gltf;
+
+use cgmath::{Matrix4, Quaternion, Vector3};
+use cgmath::prelude::*;
+
+use camera;
+use model::Model;
+
+/// Size of the bone matrix array in the shaders
+pub const MAX_BONES: usize = 100;
+pub const MAX_BONE_INFLUENCE: usize = 4;
+
+/// Local transform of a node, kept as translation, rotation and scale so that each can be
+/// interpolated on its own
+#[derive(Clone, Copy, Debug)]
+pub struct Transform {
+    pub translation: Vector3<f32>,
+    pub rotation: Quaternion<f32>,
+    pub scale: Vector3<f32>,
+}
+
+impl Transform {
+    pub fn matrix(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.translation)
+            * Matrix4::from(self.rotation)
+            * Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z)
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform {
+            translation: Vector3::zero(),
+            rotation: Quaternion::one(),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Node {
+    pub name: String,
+    /// transform relative to the parent in the bind pose
+    pub transform: Transform,
+    pub children: Vec<usize>,
+}
+
+/// A joint that deforms vertices
+#[derive(Clone, Copy, Debug)]
+pub struct BoneInfo {
+    /// index into `Skeleton::nodes`
+    pub node: usize,
+    /// inverse bind matrix, transforms vertices from model space into the space of the bone
+    pub offset: Matrix4<f32>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Skeleton {
+    /// all nodes of the glTF file (same indices)
+    pub nodes: Vec<Node>,
+    /// root nodes of the scene
+    pub roots: Vec<usize>,
+    /// the joints of all skins, `Vertex::BoneIDs` index into this
+    pub bones: Vec<BoneInfo>,
+    /// index of the first bone of each skin
+    pub skinBones: Vec<usize>,
+}
+
+impl Skeleton {
+    pub fn from_gltf(document: &gltf::Document, scene: &gltf::Scene, buffers: &[gltf::buffer::Data]) -> Skeleton {
+        let nodes = document.nodes().map(|node| {
+            let (translation, rotation, scale) = node.transform().decomposed();
+            let [x, y, z, w] = rotation;
+            Node {
+                name: node.name().unwrap_or("").into(),
+                transform: Transform {
+                    translation: translation.into(),
+                    rotation: Quaternion::new(w, x, y, z),
+                    scale: scale.into(),
+                },
+                children: node.children().map(|child| child.index()).collect(),
+            }
+        }).collect();
+
+        let mut bones = Vec::new();
+        let mut skinBones = Vec::new();
+        for skin in document.skins() {
+            skinBones.push(bones.len());
+            let reader = skin.reader(|buffer| Some(&*buffers[buffer.index()]));
+            // without inverse bind matrices the joints are already in the bind pose
+            let offsets: Vec<Matrix4<f32>> = reader.read_inverse_bind_matrices()
+                .map(|matrices| matrices.map(Matrix4::from).collect())
+                .unwrap_or_default();
+            for (i, joint) in skin.joints().enumerate() {
+                bones.push(BoneInfo {
+                    node: joint.index(),
+                    offset: offsets.get(i).cloned().unwrap_or_else(Matrix4::identity),
+                });
+            }
+        }
+        if bones.len() > MAX_BONES {
+            println!("WARNING: the model has {} bones, only {} are supported by the shaders", bones.len(), MAX_BONES);
+        }
+
+        Skeleton {
+            nodes,
+            roots: scene.nodes().map(|node| node.index()).collect(),
+            bones,
+            skinBones,
+        }
+    }
+}
+
+/// Keyframes of one property; `times` are in seconds and ascending
+#[derive(Clone, Debug)]
+pub struct Keyframes<T> {
+    pub times: Vec<f32>,
+    pub values: Vec<T>,
+    /// hold each value until the next keyframe instead of interpolating
+    pub step: bool,
+}
+
+impl<T: Copy> Keyframes<T> {
+    /// Value at `time`, clamped to the first and last keyframe
+    pub fn Sample<F: Fn(T, T, f32) -> T>(&self, time: f32, interpolate: F) -> T {
+        let last = self.times.len() - 1;
+        if time <= self.times[0] || last == 0 {
+            return self.values[0];
+        }
+        if time >= self.times[last] {
+            return self.values[last];
+        }
+        // index of the keyframe before `time`
+        let index = match self.times.binary_search_by(|t| t.partial_cmp(&time).unwrap()) {
+            Ok(index) => return self.values[index],
+            Err(index) => index - 1,
+        };
+        if self.step {
+            return self.values[index];
+        }
+        let (start, end) = (self.times[index], self.times[index + 1]);
+        let scaleFactor = (time - start) / (end - start);
+        interpolate(self.values[index], self.values[index + 1], scaleFactor)
+    }
+}
+
+/// Keyframes of a single node (learnopengl's `Bone`)
+#[derive(Clone, Debug)]
+pub struct Channel {
+    pub node: usize,
+    pub translations: Option<Keyframes<Vector3<f32>>>,
+    pub rotations: Option<Keyframes<Quaternion<f32>>>,
+    pub scales: Option<Keyframes<Vector3<f32>>>,
+}
+
+impl Channel {
+    fn new(node: usize) -> Channel {
+        Channel { node, translations: None, rotations: None, scales: None }
+    }
+
+    /// Local transform at `time`; properties without keyframes keep their value from `transform`
+    pub fn Sample(&self, time: f32, transform: &mut Transform) {
+        let lerp = |from: Vector3<f32>, to: Vector3<f32>, t: f32| from.lerp(to, t);
+        if let Some(ref translations) = self.translations {
+            transform.translation = translations.Sample(time, lerp);
+        }
+        if let Some(ref rotations) = self.rotations {
+            transform.rotation = rotations.Sample(time, camera::slerp);
+        }
+        if let Some(ref scales) = self.scales {
+            transform.scale = scales.Sample(time, lerp);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Animation {
+    pub name: String,
+    /// in seconds
+    pub duration: f32,
+    pub channels: Vec<Channel>,
+}
+
+impl Animation {
+    /// Loads all animations of a glTF file. Cubic spline keyframes are interpolated linearly
+    /// between their values (the tangents are ignored), morph target weights aren't supported.
+    pub fn from_gltf(document: &gltf::Document, buffers: &[gltf::buffer::Data]) -> Vec<Animation> {
+        use gltf::animation::util::ReadOutputs;
+
+        document.animations().map(|animation| {
+            let mut channels: Vec<Channel> = Vec::new();
+            let mut duration: f32 = 0.0;
+            for channel in animation.channels() {
+                let reader = channel.reader(|buffer| Some(&*buffers[buffer.index()]));
+                let (times, outputs) = match (reader.read_inputs(), reader.read_outputs()) {
+                    (Some(inputs), Some(outputs)) => (inputs.collect::<Vec<f32>>(), outputs),
+                    _ => continue,
+                };
+                if times.is_empty() {
+                    continue;
+                }
+                duration = duration.max(times[times.len() - 1]);
+
+                let interpolation = channel.sampler().interpolation();
+                let node = channel.target().node().index();
+                let index = match channels.iter().position(|c| c.node == node) {
+                    Some(index) => index,
+                    None => {
+                        channels.push(Channel::new(node));
+                        channels.len() - 1
+                    }
+                };
+                let target = &mut channels[index];
+                match outputs {
+                    ReadOutputs::Translations(translations) =>
+                        target.translations = Some(keyframes(&times, translations.map(Vector3::from).collect(), interpolation)),
+                    ReadOutputs::Rotations(rotations) =>
+                        target.rotations = Some(keyframes(&times, rotations.into_f32().map(|[x, y, z, w]| Quaternion::new(w, x, y, z)).collect(), interpolation)),
+                    ReadOutputs::Scales(scales) =>
+                        target.scales = Some(keyframes(&times, scales.map(Vector3::from).collect(), interpolation)),
+                    ReadOutputs::MorphTargetWeights(_) => {}
+                }
+            }
+
+            Animation {
+                name: animation.name().unwrap_or("").into(),
+                duration,
+                channels,
+            }
+        }).collect()
+    }
+}
+
+fn keyframes<T>(times: &[f32], values: Vec<T>, interpolation: gltf::animation::Interpolation) -> Keyframes<T> {
+    use gltf::animation::Interpolation;
+
+    // cubic spline samplers store in-tangent, value and out-tangent for every keyframe
+    let values = match interpolation {
+        Interpolation::CubicSpline => values.into_iter().skip(1).step_by(3).collect(),
+        _ => values,
+    };
+    Keyframes { times: times.to_vec(), values, step: interpolation == Interpolation::Step }
+}
+
+/// Plays one animation of a model and computes the bone matrices for the vertex shader
+pub struct Animator {
+    /// index into `Model::animations`
+    pub CurrentAnimation: Option<usize>,
+    /// in seconds
+    pub CurrentTime: f32,
+    /// local transform of every node in the current pose
+    pose: Vec<Transform>,
+    FinalBoneMatrices: Vec<Matrix4<f32>>,
+}
+
+impl Animator {
+    /// Starts with the first animation of the model (if any)
+    pub fn new(model: &Model) -> Animator {
+        Animator {
+            CurrentAnimation: if model.animations.is_empty() { None } else { Some(0) },
+            CurrentTime: 0.0,
+            pose: Vec::new(),
+            FinalBoneMatrices: vec![Matrix4::identity(); model.skeleton.bones.len().max(1).min(MAX_BONES)],
+        }
+    }
+
+    /// Advances the time (looping the animation) and recomputes the bone matrices
+    pub fn UpdateAnimation(&mut self, deltaTime: f32, model: &Model) {
+        let skeleton = &model.skeleton;
+        self.pose.clear();
+        self.pose.extend(skeleton.nodes.iter().map(|node| node.transform));
+
+        if let Some(animation) = self.CurrentAnimation.and_then(|index| model.animations.get(index)) {
+            self.CurrentTime += deltaTime;
+            if animation.duration > 0.0 {
+                self.CurrentTime %= animation.duration;
+            }
+            for channel in &animation.channels {
+                channel.Sample(self.CurrentTime, &mut self.pose[channel.node]);
+            }
+        }
+
+        let mut globalTransforms = vec![Matrix4::identity(); skeleton.nodes.len()];
+        for &root in &skeleton.roots {
+            self.CalculateBoneTransform(skeleton, root, Matrix4::identity(), &mut globalTransforms);
+        }
+        for (finalBoneMatrix, bone) in self.FinalBoneMatrices.iter_mut().zip(&skeleton.bones) {
+            *finalBoneMatrix = globalTransforms[bone.node] * bone.offset;
+        }
+    }
+
+    pub fn PlayAnimation(&mut self, animation: usize) {
+        self.CurrentAnimation = Some(animation);
+        self.CurrentTime = 0.0;
+    }
+
+    /// Bone matrices for `finalBonesMatrices` in the vertex shader
+    pub fn GetFinalBoneMatrices(&self) -> &[Matrix4<f32>] {
+        &self.FinalBoneMatrices
+    }
+
+    fn CalculateBoneTransform(&self, skeleton: &Skeleton, node: usize, parentTransform: Matrix4<f32>,
+                              globalTransforms: &mut Vec<Matrix4<f32>>) {
+        let globalTransform = parentTransform * self.pose[node].matrix();
+        globalTransforms[node] = globalTransform;
+        for &child in &skeleton.nodes[node].children {
+            self.CalculateBoneTransform(skeleton, child, globalTransform, globalTransforms);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gltf::animation::Interpolation;
+
+    fn lerp(a: f32, b: f32, amount: f32) -> f32 {
+        a + (b - a) * amount
+    }
+
+    fn scalarKeyframes(step: bool) -> Keyframes<f32> {
+        Keyframes { times: vec![1.0, 2.0, 4.0], values: vec![10.0, 20.0, 0.0], step }
+    }
+
+    #[test]
+    fn linear_sampling_interpolates_between_keyframes() {
+        let keyframes = scalarKeyframes(false);
+        assert_eq!(keyframes.Sample(1.5, lerp), 15.0);
+        assert_eq!(keyframes.Sample(3.0, lerp), 10.0);
+        assert_eq!(keyframes.Sample(2.0, lerp), 20.0);
+    }
+
+    #[test]
+    fn step_sampling_holds_previous_value() {
+        let keyframes = scalarKeyframes(true);
+        assert_eq!(keyframes.Sample(1.5, lerp), 10.0);
+        assert_eq!(keyframes.Sample(3.9, lerp), 20.0);
+        assert_eq!(keyframes.Sample(2.0, lerp), 20.0);
+    }
+
+    #[test]
+    fn sampling_out_of_range_clamps() {
+        for &step in &[false, true] {
+            let keyframes = scalarKeyframes(step);
+            assert_eq!(keyframes.Sample(-1.0, lerp), 10.0);
+            assert_eq!(keyframes.Sample(5.0, lerp), 0.0);
+        }
+        let single = Keyframes { times: vec![1.0], values: vec![7.0], step: false };
+        assert_eq!(single.Sample(2.0, lerp), 7.0);
+    }
+
+    #[test]
+    fn cubic_spline_keeps_values_of_triplets() {
+        // in-tangent, value and out-tangent per keyframe
+        let values = vec![-1.0, 10.0, 1.0, -2.0, 20.0, 2.0, -3.0, 30.0, 3.0];
+        let keyframes = keyframes(&[0.0, 1.0, 2.0], values, Interpolation::CubicSpline);
+        assert_eq!(keyframes.values, vec![10.0, 20.0, 30.0]);
+        assert!(!keyframes.step);
+    }
+
+    #[test]
+    fn linear_and_step_keep_all_values() {
+        let linear = keyframes(&[0.0, 1.0], vec![1.0, 2.0], Interpolation::Linear);
+        assert_eq!(linear.values, vec![1.0, 2.0]);
+        assert!(!linear.step);
+        let step = keyframes(&[0.0, 1.0], vec![1.0, 2.0], Interpolation::Step);
+        assert!(step.step);
+    }
+}
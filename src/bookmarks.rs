@@ -0,0 +1,104 @@
+#![allow(non_snake_case)]
+
+/// Viewpoint bookmarks: Shift+1 to Shift+9 saves the camera into one of nine slots per tutorial and
+/// 1 to 9 restores it. The bookmarks are kept in `bookmarks.json` next to the executable. Saving also
+/// prints the view as `--camera x,y,z,yaw,pitch`, which starts a tutorial from exactly that view, so
+/// e.g. an artifact visible from a certain position can be reproduced from a bug report.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use serde_json;
+
+use camera::{Camera, CameraState};
+use context::{Options, Window};
+use input;
+
+const FILE_NAME: &str = "bookmarks.json";
+
+/// Slots per tutorial id
+type Bookmarks = BTreeMap<String, BTreeMap<String, CameraState>>;
+
+struct State {
+    tutorial: String,
+    /// view from `--camera`, applied in the first frame
+    initial: Option<CameraState>,
+    /// whether the key of each slot was down in the last frame
+    keysPressed: [bool; 9],
+}
+
+thread_local! {
+    static STATE: RefCell<Option<State>> = RefCell::new(None);
+}
+
+/// Called for every new window
+pub fn reset(options: &Options) {
+    let state = State {
+        tutorial: options.tutorial.clone().unwrap_or_default(),
+        initial: options.camera,
+        keysPressed: [false; 9],
+    };
+    STATE.with(|s| *s.borrow_mut() = Some(state));
+}
+
+/// Applies `--camera` and handles the bookmark keys, called once per frame (see `common::processCameraInput`)
+pub fn update(window: &Window, camera: &mut Camera) {
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let state = match *state {
+            Some(ref mut state) => state,
+            None => return,
+        };
+        if let Some(initial) = state.initial.take() {
+            camera.SetState(&initial);
+        }
+
+        let save = input::pressed(window, "save_bookmark");
+        for slot in 0..9 {
+            let pressed = input::pressed(window, &format!("bookmark_{}", slot + 1));
+            if pressed && !state.keysPressed[slot] {
+                let name = (slot + 1).to_string();
+                if save {
+                    saveBookmark(&state.tutorial, &name, camera.GetState());
+                } else {
+                    match load().get(&state.tutorial).and_then(|slots| slots.get(&name)) {
+                        Some(bookmark) => camera.SetState(bookmark),
+                        None => println!("No bookmark {} for this tutorial (save it with Shift+{})", name, name),
+                    }
+                }
+            }
+            state.keysPressed[slot] = pressed;
+        }
+    })
+}
+
+fn saveBookmark(tutorial: &str, name: &str, bookmark: CameraState) {
+    let mut bookmarks = load();
+    bookmarks.entry(tutorial.to_string()).or_insert_with(BTreeMap::new).insert(name.to_string(), bookmark);
+    let path = path();
+    let result = serde_json::to_string_pretty(&bookmarks).map_err(|e| e.to_string())
+        .and_then(|json| fs::write(&path, json + "\n").map_err(|e| format!("Failed to write {}: {}", path.display(), e)));
+    match result {
+        Ok(()) => println!("Saved bookmark {}: --camera {}", name, bookmark),
+        Err(e) => println!("{}", e),
+    }
+}
+
+/// All bookmarks; missing or unreadable files are treated as empty
+fn load() -> Bookmarks {
+    let path = path();
+    match fs::read_to_string(&path) {
+        Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+            println!("Ignoring invalid {}: {}", path.display(), e);
+            Bookmarks::new()
+        }),
+        Err(_) => Bookmarks::new(),
+    }
+}
+
+fn path() -> PathBuf {
+    env::current_exe().map(|exe| exe.with_file_name(FILE_NAME)).unwrap_or_else(|_| PathBuf::from(FILE_NAME))
+}
@@ -2,13 +2,20 @@
 #![allow(non_snake_case)]
 #![allow(dead_code)]
 
+use std::fmt;
+use std::str::FromStr;
+
 use cgmath;
-use cgmath::vec3;
+use cgmath::{vec3, frustum, ortho, perspective, Deg, Rad};
 use cgmath::prelude::*;
 
+use frustum::Frustum;
+
 type Point3 = cgmath::Point3<f32>;
 type Vector3 = cgmath::Vector3<f32>;
+type Matrix3 = cgmath::Matrix3<f32>;
 type Matrix4 = cgmath::Matrix4<f32>;
+type Quaternion = cgmath::Quaternion<f32>;
 
 // Defines several possible options for camera movement. Used as abstraction to stay away from window-system specific input methods
 #[derive(PartialEq, Clone, Copy)]
@@ -17,16 +24,106 @@ pub enum Camera_Movement {
     BACKWARD,
     LEFT,
     RIGHT,
+    // only used in free mode
+    UP,
+    DOWN,
+    ROLL_LEFT,
+    ROLL_RIGHT,
 }
 
 use self::Camera_Movement::*;
 
+/// How mouse movement (and the movement keys) control the camera
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum CameraMode {
+    /// first person camera driven by Euler angles (the original behaviour)
+    Fly,
+    /// rotates around `Target` at `Distance`; scrolling changes the distance instead of the field of view
+    Orbit,
+    /// like `Orbit`, but rotates around the view space axis perpendicular to the mouse movement,
+    /// so there's no gimbal lock at the poles and the camera can go upside down
+    Arcball,
+    /// 6-DOF flight backed by `Orientation`: mouse movement turns around the camera's own axes
+    /// without limiting the pitch, and the camera can roll and move up/down
+    Free,
+}
+
+/// How the camera projects onto the screen, see `GetProjectionMatrix`
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Projection {
+    /// the usual perspective projection with a vertical field of view of `Zoom` degrees
+    Perspective { near: f32, far: f32 },
+    /// perspective projection with the far plane at infinity that maps the near plane to depth 1 and
+    /// infinity to 0, so depth precision is spread evenly over large distances. Requires clearing the
+    /// depth to 0 and the `GREATER` depth test (see `utils::set_reversed_z`).
+    ReversedZ { near: f32 },
+    /// parallel projection showing `height` units vertically at the default zoom; scrolling zooms in
+    Orthographic { height: f32, near: f32, far: f32 },
+    /// asymmetric frustum given by the extents of the near plane (as with `glFrustum`), e.g. for stereo
+    /// pairs or tiled screenshots. Ignores the aspect ratio and the zoom.
+    OffAxis { left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32 },
+}
+
+/// The part of the camera that defines the view, e.g. for bookmarks (see bookmarks.rs). Parses from
+/// and prints as `x,y,z,yaw,pitch` (optionally followed by the zoom), the format of `--camera`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CameraState {
+    pub position: [f32; 3],
+    pub yaw: f32,
+    pub pitch: f32,
+    #[serde(default)]
+    pub roll: f32,
+    pub zoom: f32,
+    pub speed: f32,
+}
+
+impl fmt::Display for CameraState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [x, y, z] = self.position;
+        write!(f, "{},{},{},{},{}", x, y, z, self.yaw, self.pitch)?;
+        if self.zoom != ZOOM {
+            write!(f, ",{}", self.zoom)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for CameraState {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<CameraState, String> {
+        let values = s.split(',').map(|value| value.trim().parse::<f32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| format!("invalid camera `{}` (expected `x,y,z,yaw,pitch[,zoom]`)", s))?;
+        if values.len() != 5 && values.len() != 6 {
+            return Err(format!("invalid camera `{}` (expected `x,y,z,yaw,pitch[,zoom]`)", s));
+        }
+        Ok(CameraState {
+            position: [values[0], values[1], values[2]],
+            yaw: values[3],
+            pitch: values[4],
+            roll: 0.0,
+            zoom: values.get(5).cloned().unwrap_or(ZOOM),
+            speed: SPEED,
+        })
+    }
+}
+
 // Default camera values
 const YAW: f32 = -90.0;
 const PITCH: f32 = 0.0;
 const SPEED: f32 = 2.5;
 const SENSITIVTY: f32 = 0.1;
 const ZOOM: f32 = 45.0;
+const NEAR: f32 = 0.1;
+const FAR: f32 = 100.0;
+/// degrees per second
+const ROLL_SPEED: f32 = 90.0;
+const MIN_DISTANCE: f32 = 0.1;
+/// relative change of the orbit distance per scroll step
+const DISTANCE_ZOOM_STEP: f32 = 0.1;
+/// pan distance per pixel, relative to the distance to the target
+const PAN_SENSITIVITY: f32 = 0.002;
 
 pub struct Camera {
     // Camera Attributes
@@ -38,10 +135,25 @@ pub struct Camera {
     // Euler Angles
     pub Yaw: f32,
     pub Pitch: f32,
+    /// only non-zero in free mode, see `SetEulerAngles`
+    pub Roll: f32,
     // Camera options
     pub MovementSpeed: f32,
     pub MouseSensitivity: f32,
     pub Zoom: f32,
+    pub Projection: Projection,
+    // Orbit/arcball options
+    pub Mode: CameraMode,
+    pub Target: Point3,
+    pub Distance: f32,
+    /// rotation of the camera in arcball and free mode
+    pub Orientation: Quaternion,
+    /// in free mode, rotations are applied to this and `Orientation` follows it smoothly (see `Update`)
+    pub TargetOrientation: Quaternion,
+    /// time in seconds for `Orientation` to get most of the way to `TargetOrientation`; 0 turns it off
+    pub RotationSmoothing: f32,
+    /// while set (e.g. when the right mouse button is held), mouse movement pans instead of rotating
+    pub Panning: bool,
 }
 
 impl Default for Camera {
@@ -54,9 +166,18 @@ impl Default for Camera {
             WorldUp: Vector3::unit_y(),
             Yaw: YAW,
             Pitch: PITCH,
+            Roll: 0.0,
             MovementSpeed: SPEED,
             MouseSensitivity: SENSITIVTY,
             Zoom: ZOOM,
+            Projection: Projection::Perspective { near: NEAR, far: FAR },
+            Mode: CameraMode::Fly,
+            Target: Point3::new(0.0, 0.0, 0.0),
+            Distance: 0.0,
+            Orientation: Quaternion::one(),
+            TargetOrientation: Quaternion::one(),
+            RotationSmoothing: 0.0,
+            Panning: false,
         };
         camera.updateCameraVectors();
         camera
@@ -69,9 +190,68 @@ impl Camera {
         Matrix4::look_at(self.Position, self.Position + self.Front, self.Up)
     }
 
+    /// Returns the projection matrix for the given aspect ratio (width / height)
+    pub fn GetProjectionMatrix(&self, aspect: f32) -> Matrix4 {
+        match self.Projection {
+            Projection::Perspective { near, far } => perspective(Deg(self.Zoom), aspect, near, far),
+            Projection::ReversedZ { near } => {
+                let f = 1.0 / (Rad::from(Deg(self.Zoom)).0 / 2.0).tan();
+                // clip space z is always `near` and w is the distance, so NDC depth is near / distance
+                Matrix4::new(
+                    f / aspect, 0.0, 0.0, 0.0,
+                    0.0, f, 0.0, 0.0,
+                    0.0, 0.0, 0.0, -1.0,
+                    0.0, 0.0, near, 0.0)
+            }
+            Projection::Orthographic { height, near, far } => {
+                let top = height * self.Zoom / ZOOM / 2.0;
+                let right = top * aspect;
+                ortho(-right, right, -top, top, near, far)
+            }
+            Projection::OffAxis { left, right, bottom, top, near, far } => frustum(left, right, bottom, top, near, far),
+        }
+    }
+
+    /// Returns the projection matrix multiplied with the view matrix
+    pub fn GetViewProjection(&self, aspect: f32) -> Matrix4 {
+        self.GetProjectionMatrix(aspect) * self.GetViewMatrix()
+    }
+
+    /// Returns the world space view frustum for culling
+    pub fn GetFrustum(&self, aspect: f32) -> Frustum {
+        Frustum::from_matrix(&self.GetViewProjection(aspect))
+    }
+
     /// Processes input received from any keyboard-like input system. Accepts input parameter in the form of camera defined ENUM (to abstract it from windowing systems)
+    /// In orbit and arcball mode, forward/backward change the distance and left/right/up/down pan the target.
     pub fn ProcessKeyboard(&mut self, direction: Camera_Movement, deltaTime: f32) {
         let velocity = self.MovementSpeed * deltaTime;
+        match self.Mode {
+            CameraMode::Orbit | CameraMode::Arcball => {
+                match direction {
+                    FORWARD => self.Distance = (self.Distance - velocity).max(MIN_DISTANCE),
+                    BACKWARD => self.Distance += velocity,
+                    LEFT => self.Target += -(self.Right * velocity),
+                    RIGHT => self.Target += self.Right * velocity,
+                    UP => self.Target += self.Up * velocity,
+                    DOWN => self.Target += -(self.Up * velocity),
+                    ROLL_LEFT | ROLL_RIGHT => {}
+                }
+                self.updatePosition();
+                return;
+            }
+            CameraMode::Free => {
+                let roll = ROLL_SPEED * deltaTime;
+                match direction {
+                    UP => self.Position += self.Up * velocity,
+                    DOWN => self.Position += -(self.Up * velocity),
+                    ROLL_LEFT => self.rotateLocal(Vector3::unit_z(), roll),
+                    ROLL_RIGHT => self.rotateLocal(Vector3::unit_z(), -roll),
+                    _ => {}
+                }
+            }
+            CameraMode::Fly => {}
+        }
         if direction == FORWARD {
             self.Position += self.Front * velocity;
         }
@@ -91,6 +271,24 @@ impl Camera {
         xoffset *= self.MouseSensitivity;
         yoffset *= self.MouseSensitivity;
 
+        if self.Mode == CameraMode::Arcball {
+            // rotate around the view space axis perpendicular to the movement (in the opposite direction,
+            // so the target appears to follow the mouse)
+            let angle = (xoffset * xoffset + yoffset * yoffset).sqrt();
+            if angle > 0.0 {
+                let axis = self.Orientation * vec3(-yoffset, xoffset, 0.0).normalize();
+                self.Orientation = (Quaternion::from_axis_angle(axis, Deg(-angle)) * self.Orientation).normalize();
+                self.updateOrientationVectors();
+            }
+            return;
+        }
+        if self.Mode == CameraMode::Free {
+            // turn around the camera's own up and right axes, so the pitch isn't limited
+            self.rotateLocal(Vector3::unit_y(), -xoffset);
+            self.rotateLocal(Vector3::unit_x(), yoffset);
+            return;
+        }
+
         self.Yaw += xoffset;
         self.Pitch += yoffset;
 
@@ -106,10 +304,31 @@ impl Camera {
 
         // Update Front, Right and Up Vectors using the updated Eular angles
         self.updateCameraVectors();
+        if self.Mode == CameraMode::Orbit {
+            self.updatePosition();
+        }
+    }
+
+    /// Moves the camera (and the target) parallel to the view plane, so the scene follows the mouse
+    pub fn ProcessMousePan(&mut self, xoffset: f32, yoffset: f32) {
+        let distance = match self.Mode {
+            CameraMode::Orbit | CameraMode::Arcball => self.Distance,
+            CameraMode::Fly | CameraMode::Free => (self.Target - self.Position).magnitude(),
+        };
+        let scale = PAN_SENSITIVITY * distance.max(1.0);
+        let offset = -(self.Right * xoffset + self.Up * yoffset) * scale;
+        self.Position += offset;
+        self.Target += offset;
     }
 
     // Processes input received from a mouse scroll-wheel event. Only requires input on the vertical wheel-axis
+    // In orbit and arcball mode it changes the distance to the target instead of the field of view.
     pub fn ProcessMouseScroll(&mut self, yoffset: f32) {
+        if self.Mode == CameraMode::Orbit || self.Mode == CameraMode::Arcball {
+            self.Distance = (self.Distance * (1.0 - DISTANCE_ZOOM_STEP * yoffset)).max(MIN_DISTANCE);
+            self.updatePosition();
+            return;
+        }
         if self.Zoom >= 1.0 && self.Zoom <= 45.0 {
             self.Zoom -= yoffset;
         }
@@ -121,6 +340,157 @@ impl Camera {
         }
     }
 
+    /// Moves `Orientation` towards `TargetOrientation` in free mode; call once per frame when using `RotationSmoothing`
+    pub fn Update(&mut self, deltaTime: f32) {
+        if self.Mode != CameraMode::Free || self.RotationSmoothing <= 0.0 {
+            return;
+        }
+        // frame rate independent exponential smoothing
+        let amount = 1.0 - (-deltaTime / self.RotationSmoothing).exp();
+        self.Orientation = slerp(self.Orientation, self.TargetOrientation, amount);
+        self.updateOrientationVectors();
+    }
+
+    pub fn GetState(&self) -> CameraState {
+        CameraState {
+            position: self.Position.into(),
+            yaw: self.Yaw,
+            pitch: self.Pitch,
+            roll: self.Roll,
+            zoom: self.Zoom,
+            speed: self.MovementSpeed,
+        }
+    }
+
+    /// Restores a saved view; in orbit and arcball mode the target is moved instead of the camera
+    pub fn SetState(&mut self, state: &CameraState) {
+        self.Zoom = state.zoom;
+        self.MovementSpeed = state.speed;
+        self.SetEulerAngles(state.yaw, state.pitch, state.roll);
+        self.Position = Point3::from(state.position);
+        if self.Mode == CameraMode::Orbit || self.Mode == CameraMode::Arcball {
+            self.Target = self.Position + self.Front * self.Distance;
+        }
+    }
+
+    /// Switches the mode. The orbit modes look at `Target` from the current position.
+    pub fn SetMode(&mut self, mode: CameraMode) {
+        if mode == self.Mode {
+            return;
+        }
+        match mode {
+            CameraMode::Fly => {
+                // keep looking in the same direction (the roll is lost)
+                self.anglesFromFront(self.Front);
+                self.Pitch = self.Pitch.max(-89.0).min(89.0);
+                self.Roll = 0.0;
+                self.updateCameraVectors();
+            }
+            CameraMode::Orbit | CameraMode::Arcball => {
+                let toTarget = self.Target - self.Position;
+                self.Distance = toTarget.magnitude().max(MIN_DISTANCE);
+                if toTarget.magnitude() > 0.0 {
+                    self.anglesFromFront(toTarget.normalize());
+                }
+                self.Pitch = self.Pitch.max(-89.0).min(89.0);
+                self.Roll = 0.0;
+                self.updateCameraVectors();
+            }
+            CameraMode::Free => {}
+        }
+        self.Orientation = self.GetOrientation();
+        self.TargetOrientation = self.Orientation;
+        self.Mode = mode;
+        self.updatePosition();
+    }
+
+    /// Cycles through fly -> orbit -> arcball -> free
+    pub fn NextMode(&mut self) {
+        let next = match self.Mode {
+            CameraMode::Fly => CameraMode::Orbit,
+            CameraMode::Orbit => CameraMode::Arcball,
+            CameraMode::Arcball => CameraMode::Free,
+            CameraMode::Free => CameraMode::Fly,
+        };
+        self.SetMode(next);
+    }
+
+    /// Rotation from camera space (looking along -Z with Y up) to world space, in any mode
+    pub fn GetOrientation(&self) -> Quaternion {
+        Quaternion::from(Matrix3::from_cols(self.Right, self.Up, -self.Front)).normalize()
+    }
+
+    /// Sets the orientation directly; only free mode can keep a roll, the other modes keep the view direction
+    pub fn SetOrientation(&mut self, orientation: Quaternion) {
+        match self.Mode {
+            CameraMode::Free | CameraMode::Arcball => {
+                self.Orientation = orientation.normalize();
+                self.TargetOrientation = self.Orientation;
+                self.updateOrientationVectors();
+            }
+            CameraMode::Fly | CameraMode::Orbit => {
+                self.anglesFromFront(orientation * vec3(0.0, 0.0, -1.0));
+                self.updateCameraVectors();
+                self.updatePosition();
+            }
+        }
+    }
+
+    /// Euler angle facade for all modes (in degrees; the roll is ignored outside of free mode)
+    pub fn SetEulerAngles(&mut self, yaw: f32, pitch: f32, roll: f32) {
+        self.Yaw = yaw;
+        self.Pitch = pitch;
+        self.Roll = roll;
+        self.updateCameraVectors();
+        if self.Mode == CameraMode::Free {
+            let orientation = self.GetOrientation() * Quaternion::from_angle_z(Deg(-roll));
+            self.SetOrientation(orientation);
+        } else {
+            self.Roll = 0.0;
+            self.updatePosition();
+        }
+    }
+
+    /// Rotates the (target) orientation around one of the camera's own axes, by degrees
+    fn rotateLocal(&mut self, axis: Vector3, angle: f32) {
+        self.TargetOrientation = (self.TargetOrientation * Quaternion::from_axis_angle(axis, Deg(angle))).normalize();
+        if self.RotationSmoothing <= 0.0 {
+            self.Orientation = self.TargetOrientation;
+            self.updateOrientationVectors();
+        }
+    }
+
+    /// Sets `Yaw` and `Pitch` (unclamped) from a view direction
+    fn anglesFromFront(&mut self, front: Vector3) {
+        self.Yaw = Deg::from(Rad(front.z.atan2(front.x))).0;
+        self.Pitch = Deg::from(Rad(front.y.max(-1.0).min(1.0).asin())).0;
+    }
+
+    /// Puts the camera at `Distance` from the target in orbit and arcball mode
+    fn updatePosition(&mut self) {
+        if self.Mode == CameraMode::Orbit || self.Mode == CameraMode::Arcball {
+            self.Position = self.Target - self.Front * self.Distance;
+        }
+    }
+
+    /// Calculates the camera vectors from `Orientation` (arcball and free mode) and keeps the
+    /// Euler angles in sync with them
+    fn updateOrientationVectors(&mut self) {
+        self.Front = (self.Orientation * vec3(0.0, 0.0, -1.0)).normalize();
+        self.Up = (self.Orientation * Vector3::unit_y()).normalize();
+        self.Right = (self.Orientation * Vector3::unit_x()).normalize();
+        self.updatePosition();
+
+        // the roll is the angle between the right vector and the horizon; undefined when looking straight up or down
+        let levelRight = self.Front.cross(self.WorldUp);
+        if levelRight.magnitude() > 1e-4 {
+            let levelRight = levelRight.normalize();
+            let levelUp = levelRight.cross(self.Front);
+            self.anglesFromFront(self.Front);
+            self.Roll = Deg::from(Rad((-self.Right.dot(levelUp)).atan2(self.Right.dot(levelRight)))).0;
+        }
+    }
+
     /// Calculates the front vector from the Camera's (updated) Eular Angles
     fn updateCameraVectors(&mut self) {
         // Calculate the new Front vector
@@ -135,3 +505,51 @@ impl Camera {
         self.Up = self.Right.cross(self.Front).normalize();
     }
 }
+
+/// Spherical interpolation along the shorter arc (`Quaternion::slerp` may take the long way around)
+pub fn slerp(from: Quaternion, to: Quaternion, amount: f32) -> Quaternion {
+    let to = if from.dot(to) < 0.0 { -to } else { to };
+    from.slerp(to, amount).normalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(zoom: f32) -> CameraState {
+        CameraState { position: [1.5, -2.0, 0.25], yaw: 30.0, pitch: -20.5, roll: 0.0, zoom, speed: SPEED }
+    }
+
+    #[test]
+    fn camera_state_round_trips() {
+        for &zoom in &[ZOOM, 30.0] {
+            let state = state(zoom);
+            assert_eq!(state.to_string().parse::<CameraState>(), Ok(state));
+        }
+    }
+
+    #[test]
+    fn camera_state_prints_zoom_only_if_changed() {
+        assert_eq!(state(ZOOM).to_string(), "1.5,-2,0.25,30,-20.5");
+        assert_eq!(state(30.0).to_string(), "1.5,-2,0.25,30,-20.5,30");
+    }
+
+    #[test]
+    fn camera_state_parses_with_spaces() {
+        assert_eq!(" 1.5, -2, 0.25, 30, -20.5 ".parse::<CameraState>(), Ok(state(ZOOM)));
+    }
+
+    #[test]
+    fn camera_state_rejects_wrong_number_of_fields() {
+        assert!("1,2,3,4".parse::<CameraState>().is_err());
+        assert!("1,2,3,4,5,6,7".parse::<CameraState>().is_err());
+        assert!("".parse::<CameraState>().is_err());
+    }
+
+    #[test]
+    fn camera_state_rejects_non_numeric_fields() {
+        assert!("1,2,z,4,5".parse::<CameraState>().is_err());
+        assert!("1,2,3,4,5,wide".parse::<CameraState>().is_err());
+        assert!("1,2,3,,5".parse::<CameraState>().is_err());
+    }
+}
@@ -0,0 +1,369 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+
+/// Camera fly-throughs. With `--record-path <file>`, K (the `record_keyframe` action) adds the current camera position, orientation
+/// and field of view as a keyframe (the file is rewritten after each one). With `--play-path <file>`,
+/// the camera follows a Catmull-Rom spline through the keyframes at constant speed and the window
+/// is closed at the end. Together with `--fixed-dt` and `--capture` this renders the same fly-through
+/// frame for frame on every run, e.g. to compare branches.
+///
+/// Paths are stored as JSON and can be edited by hand:
+/// `{ "speed": 2.5, "keyframes": [ { "position": [x, y, z], "orientation": [x, y, z, w], "zoom": 45.0 }, ... ] }`
+/// where `orientation` is the rotation from camera to world space (see `Camera::GetOrientation`).
+
+use std::cell::{Cell, RefCell};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use cgmath::{Point3, Quaternion, Vector3};
+use cgmath::prelude::*;
+
+use serde_json;
+
+use camera;
+use camera::{Camera, CameraMode};
+use context::{Options, Window};
+use input;
+
+/// Number of samples per segment in the arc length table
+const SAMPLES_PER_SEGMENT: usize = 32;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Keyframe {
+    pub position: [f32; 3],
+    /// quaternion as [x, y, z, w]
+    pub orientation: [f32; 4],
+    /// field of view in degrees (`Camera::Zoom`)
+    pub zoom: f32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CameraPath {
+    /// playback speed in units per second
+    pub speed: f32,
+    pub keyframes: Vec<Keyframe>,
+}
+
+impl CameraPath {
+    pub fn new(speed: f32) -> CameraPath {
+        CameraPath { speed, keyframes: Vec::new() }
+    }
+
+    pub fn load(path: &Path) -> Result<CameraPath, String> {
+        let json = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let cameraPath: CameraPath = serde_json::from_str(&json).map_err(|e| format!("{}: {}", path.display(), e))?;
+        if cameraPath.keyframes.len() < 2 {
+            return Err(format!("{} needs at least two keyframes", path.display()));
+        }
+        if cameraPath.speed.is_nan() || cameraPath.speed <= 0.0 {
+            return Err(format!("{}: the speed must be positive", path.display()));
+        }
+        Ok(cameraPath)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, json + "\n").map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// Appends the current pose of the camera
+    pub fn push(&mut self, camera: &Camera) {
+        let orientation = camera.GetOrientation();
+        let (v, s) = (orientation.v, orientation.s);
+        self.keyframes.push(Keyframe {
+            position: camera.Position.into(),
+            orientation: [v.x, v.y, v.z, s],
+            zoom: camera.Zoom,
+        });
+    }
+
+    pub fn segments(&self) -> usize {
+        self.keyframes.len().saturating_sub(1)
+    }
+
+    /// Position on the segment from keyframe `segment` to the next one at `t` in [0, 1]. The tangents
+    /// at the first and last keyframe are those of a duplicated end point.
+    pub fn position(&self, segment: usize, t: f32) -> Point3<f32> {
+        let last = self.keyframes.len() - 1;
+        let point = |i: usize| Vector3::from(self.keyframes[i.min(last)].position);
+        let (p0, p1, p2, p3) = (point(segment.max(1) - 1), point(segment), point(segment + 1), point(segment + 2));
+
+        // uniform Catmull-Rom spline
+        let (t2, t3) = (t * t, t * t * t);
+        let p = (p1 * 2.0 + (p2 - p0) * t + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2 + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3) * 0.5;
+        Point3::from_vec(p)
+    }
+
+    pub fn orientation(&self, segment: usize, t: f32) -> Quaternion<f32> {
+        let quaternion = |i: usize| {
+            let [x, y, z, w] = self.keyframes[i].orientation;
+            Quaternion::new(w, x, y, z).normalize()
+        };
+        camera::slerp(quaternion(segment), quaternion(segment + 1), t)
+    }
+
+    pub fn zoom(&self, segment: usize, t: f32) -> f32 {
+        let (from, to) = (self.keyframes[segment].zoom, self.keyframes[segment + 1].zoom);
+        from + (to - from) * t
+    }
+}
+
+/// Plays a path back with constant speed. The spline parameter isn't proportional to the distance
+/// travelled, so it's looked up in a table of arc lengths sampled along the path.
+pub struct Playback {
+    path: CameraPath,
+    /// distance from the start to each sample; sample i is at spline parameter i / SAMPLES_PER_SEGMENT
+    lengths: Vec<f32>,
+    /// seconds since the start
+    time: f32,
+}
+
+impl Playback {
+    pub fn new(path: CameraPath) -> Playback {
+        let samples = path.segments() * SAMPLES_PER_SEGMENT;
+        let mut lengths = Vec::with_capacity(samples + 1);
+        let mut length = 0.0;
+        let mut previous = path.position(0, 0.0);
+        lengths.push(0.0);
+        for i in 1..=samples {
+            let (segment, t) = Playback::segmentAt(&path, i as f32 / SAMPLES_PER_SEGMENT as f32);
+            let position = path.position(segment, t);
+            length += (position - previous).magnitude();
+            lengths.push(length);
+            previous = position;
+        }
+        Playback { path, lengths, time: 0.0 }
+    }
+
+    pub fn length(&self) -> f32 {
+        *self.lengths.last().unwrap()
+    }
+
+    /// Duration in seconds; paths that only rotate in place take one second per keyframe
+    pub fn duration(&self) -> f32 {
+        if self.length() > 1e-4 {
+            self.length() / self.path.speed
+        } else {
+            self.path.segments() as f32
+        }
+    }
+
+    /// Spline parameter (the segment index plus the position within it) after `time` seconds
+    pub fn parameterAt(&self, time: f32) -> f32 {
+        let segments = self.path.segments() as f32;
+        let fraction = (time / self.duration()).max(0.0).min(1.0);
+        if self.length() <= 1e-4 {
+            return fraction * segments;
+        }
+
+        let distance = fraction * self.length();
+        let i = match self.lengths.binary_search_by(|length| length.partial_cmp(&distance).unwrap()) {
+            Ok(i) => return i as f32 / SAMPLES_PER_SEGMENT as f32,
+            Err(i) => i.max(1).min(self.lengths.len() - 1),
+        };
+        let (before, after) = (self.lengths[i - 1], self.lengths[i]);
+        let t = if after > before { (distance - before) / (after - before) } else { 0.0 };
+        (i as f32 - 1.0 + t) / SAMPLES_PER_SEGMENT as f32
+    }
+
+    /// Moves the camera to its pose after `time` seconds
+    pub fn apply(&self, time: f32, camera: &mut Camera) {
+        let (segment, t) = Playback::segmentAt(&self.path, self.parameterAt(time));
+        // only free mode keeps the roll
+        camera.SetMode(CameraMode::Free);
+        camera.SetOrientation(self.path.orientation(segment, t));
+        camera.Position = self.path.position(segment, t);
+        camera.Zoom = self.path.zoom(segment, t);
+    }
+
+    /// Advances the time and moves the camera; returns false once the end of the path was reached
+    pub fn advance(&mut self, deltaTime: f32, camera: &mut Camera) -> bool {
+        self.time += deltaTime;
+        self.apply(self.time, camera);
+        self.time < self.duration()
+    }
+
+    fn segmentAt(path: &CameraPath, parameter: f32) -> (usize, f32) {
+        let segment = (parameter.floor().max(0.0) as usize).min(path.segments() - 1);
+        (segment, parameter - segment as f32)
+    }
+}
+
+enum State {
+    Idle,
+    Recording { file: PathBuf, path: CameraPath, keyPressed: bool },
+    Playing(Playback),
+}
+
+thread_local! {
+    static STATE: RefCell<State> = RefCell::new(State::Idle);
+    /// whether the tutorial called `update` since the last `reset`
+    static UPDATED: Cell<bool> = Cell::new(false);
+}
+
+/// Starts recording or playing back from the beginning; called for every new window
+pub fn reset(options: &Options) {
+    let state = match (&options.record_path, &options.camera_path) {
+        (_, &Some(ref path)) => State::Playing(Playback::new(path.clone())),
+        (&Some(ref file), _) => State::Recording { file: file.clone(), path: CameraPath::new(0.0), keyPressed: false },
+        _ => State::Idle,
+    };
+    STATE.with(|s| *s.borrow_mut() = state);
+    UPDATED.with(|u| u.set(false));
+}
+
+/// Exits with an error if a camera path is recorded or played back, but the tutorial doesn't call
+/// `update` (because it doesn't use `Camera`, like the first camera tutorials); called after the first frame
+pub fn check_supported() {
+    let active = STATE.with(|s| match *s.borrow() {
+        State::Idle => false,
+        _ => true,
+    });
+    if active && !UPDATED.with(|u| u.get()) {
+        println!("This tutorial doesn't support --record-path and --play-path (it doesn't use the Camera struct)");
+        ::std::process::exit(1);
+    }
+}
+
+/// Records or plays back the camera path selected on the command line, called once per frame after
+/// the regular input processing (see `common::processCameraInput`)
+pub fn update(window: &mut Window, deltaTime: f32, camera: &mut Camera) {
+    UPDATED.with(|u| u.set(true));
+    STATE.with(|state| match *state.borrow_mut() {
+        State::Idle => {}
+        State::Recording { ref file, ref mut path, ref mut keyPressed } => {
+            let pressed = input::pressed(window, "record_keyframe");
+            if pressed && !*keyPressed {
+                path.speed = camera.MovementSpeed;
+                path.push(camera);
+                match path.save(file) {
+                    Ok(()) => println!("Added keyframe {} to {}", path.keyframes.len(), file.display()),
+                    Err(e) => println!("{}", e),
+                }
+            }
+            *keyPressed = pressed;
+        }
+        State::Playing(ref mut playback) => {
+            if !playback.advance(deltaTime, camera) {
+                window.set_should_close(true);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyframe(x: f32, zoom: f32) -> Keyframe {
+        Keyframe { position: [x, 0.0, 0.0], orientation: [0.0, 0.0, 0.0, 1.0], zoom }
+    }
+
+    fn path(xs: &[f32]) -> CameraPath {
+        CameraPath { speed: 2.0, keyframes: xs.iter().map(|&x| keyframe(x, 45.0)).collect() }
+    }
+
+    fn assert_close(actual: f32, expected: f32, epsilon: f32) {
+        assert!((actual - expected).abs() <= epsilon, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn spline_passes_through_keyframes() {
+        let path = CameraPath {
+            speed: 1.0,
+            keyframes: vec![keyframe(0.0, 45.0), Keyframe { position: [1.0, 2.0, 3.0], ..keyframe(0.0, 45.0) },
+                            keyframe(-4.0, 45.0)],
+        };
+        assert_eq!(path.segments(), 2);
+        assert_eq!(path.position(0, 0.0), Point3::new(0.0, 0.0, 0.0));
+        assert_eq!(path.position(0, 1.0), Point3::new(1.0, 2.0, 3.0));
+        assert_eq!(path.position(1, 0.0), Point3::new(1.0, 2.0, 3.0));
+        assert_eq!(path.position(1, 1.0), Point3::new(-4.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn catmull_rom_segments() {
+        let path = path(&[0.0, 1.0, 2.0, 3.0]);
+        // evenly spaced points on a line: the inner segment is linear
+        assert_close(path.position(1, 0.5).x, 1.5, 1e-6);
+        // the first segment uses the duplicated first point as p0:
+        // 0.5 * ((p2 - p0) t + (2 p0 - 5 p1 + 4 p2 - p3) t² + (3 p1 - p0 - 3 p2 + p3) t³) at t = 0.5
+        // = 0.5 * (0.5 + 2 * 0.25 - 1 * 0.125) = 0.4375
+        assert_close(path.position(0, 0.5).x, 0.4375, 1e-6);
+        // and the last one the duplicated last point as p3
+        assert_close(path.position(2, 0.5).x, 2.5625, 1e-6);
+    }
+
+    #[test]
+    fn zoom_and_orientation_are_interpolated() {
+        let mut path = path(&[0.0, 1.0]);
+        path.keyframes[1].zoom = 25.0;
+        // 90° around y
+        let half = (0.5f32).sqrt();
+        path.keyframes[1].orientation = [0.0, half, 0.0, half];
+        assert_close(path.zoom(0, 0.25), 40.0, 1e-6);
+        let orientation = path.orientation(0, 0.5);
+        let expected = Quaternion::from_angle_y(cgmath::Deg(45.0));
+        assert_close(orientation.dot(expected).abs(), 1.0, 1e-5);
+    }
+
+    #[test]
+    fn arc_length_table() {
+        let playback = Playback::new(path(&[0.0, 1.0, 3.0]));
+        assert_eq!(playback.lengths.len(), 2 * SAMPLES_PER_SEGMENT + 1);
+        // the spline overshoots slightly between unevenly spaced points, so the length is a bit above 3
+        assert!(playback.length() >= 3.0 && playback.length() < 3.1, "{}", playback.length());
+        assert_close(playback.duration(), playback.length() / 2.0, 1e-6);
+        assert!(playback.lengths.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn playback_has_constant_speed() {
+        // the keyframes are unevenly spaced, so the spline parameter isn't proportional to the distance
+        let playback = Playback::new(path(&[0.0, 1.0, 3.0]));
+        let duration = playback.duration();
+        let distance = |time: f32| {
+            let (segment, t) = Playback::segmentAt(&playback.path, playback.parameterAt(time));
+            let mut distance = 0.0;
+            let mut previous = playback.path.position(0, 0.0);
+            // walk along the path in small steps up to the position
+            let steps = 1000;
+            let parameter = segment as f32 + t;
+            for i in 1..=steps {
+                let (s, u) = Playback::segmentAt(&playback.path, parameter * i as f32 / steps as f32);
+                let position = playback.path.position(s, u);
+                distance += (position - previous).magnitude();
+                previous = position;
+            }
+            distance
+        };
+        for &fraction in &[0.1, 0.25, 0.5, 0.75, 0.9] {
+            assert_close(distance(fraction * duration), fraction * playback.length(), 0.01);
+        }
+        assert_close(playback.parameterAt(0.0), 0.0, 1e-6);
+        assert_close(playback.parameterAt(duration), 2.0, 1e-6);
+        assert_close(playback.parameterAt(2.0 * duration), 2.0, 1e-6);
+    }
+
+    #[test]
+    fn rotation_only_path_takes_a_second_per_keyframe() {
+        let mut path = path(&[1.0, 1.0, 1.0]);
+        path.keyframes[1].orientation = [0.0, 1.0, 0.0, 0.0];
+        let playback = Playback::new(path);
+        assert_close(playback.length(), 0.0, 1e-6);
+        assert_close(playback.duration(), 2.0, 1e-6);
+        assert_close(playback.parameterAt(0.5), 0.5, 1e-6);
+    }
+
+    #[test]
+    fn advance_ends_at_the_last_keyframe() {
+        let mut playback = Playback::new(path(&[0.0, 4.0]));
+        let mut camera = Camera::default();
+        // 4 units at 2 units per second
+        assert!(playback.advance(1.0, &mut camera));
+        assert_close(camera.Position.x, 2.0, 0.01);
+        assert!(!playback.advance(1.0, &mut camera));
+        assert_close(camera.Position.x, 4.0, 1e-5);
+        assert_eq!(camera.Mode, CameraMode::Free);
+    }
+}
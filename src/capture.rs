@@ -0,0 +1,164 @@
+#![allow(non_snake_case)]
+
+/// Screenshots and frame sequences: reads back the default framebuffer or a named FBO and saves it
+/// as PNG, or as Radiance HDR for floating point render targets (e.g. in 5.6 HDR and 5.7 Bloom), so
+/// no HDR data is lost. Triggered with F12 or for a range of frames with `--capture <dir>` (see context.rs).
+
+use std::cell::RefCell;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::os::raw::c_void;
+use std::path::{Path, PathBuf};
+
+use gl;
+use gl::types::*;
+
+use image;
+use image::{Rgb, RgbaImage};
+use image::hdr::HDREncoder;
+
+/// Default directory for screenshots taken with F12 when no `--capture` directory is given
+pub const DEFAULT_DIR: &str = "screenshots";
+
+/// A framebuffer color attachment that can be captured by name with `--capture-fbo <name>`
+struct NamedFramebuffer {
+    name: String,
+    fbo: u32,
+    attachment: GLenum,
+}
+
+thread_local! {
+    static FRAMEBUFFERS: RefCell<Vec<NamedFramebuffer>> = RefCell::new(Vec::new());
+}
+
+/// Makes a color attachment of a framebuffer available for capturing. Registrations are reset when a
+/// new window is created, so tutorials simply register their FBOs after creating them.
+pub fn register_framebuffer(name: &str, fbo: u32, attachment: GLenum) {
+    FRAMEBUFFERS.with(|framebuffers| {
+        let mut framebuffers = framebuffers.borrow_mut();
+        framebuffers.retain(|f| f.name != name);
+        framebuffers.push(NamedFramebuffer { name: name.into(), fbo, attachment });
+    });
+}
+
+pub fn clear_framebuffers() {
+    FRAMEBUFFERS.with(|framebuffers| framebuffers.borrow_mut().clear());
+}
+
+pub fn framebuffer_names() -> Vec<String> {
+    FRAMEBUFFERS.with(|framebuffers| framebuffers.borrow().iter().map(|f| f.name.clone()).collect())
+}
+
+pub enum Frame {
+    Ldr(RgbaImage),
+    Hdr { width: u32, height: u32, pixels: Vec<Rgb<f32>> },
+}
+
+impl Frame {
+    /// Saves the frame as `<path>.png` or `<path>.hdr` and returns the full path
+    pub fn save(&self, path: &Path) -> Result<PathBuf, String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        match *self {
+            Frame::Ldr(ref image) => {
+                let path = path.with_extension("png");
+                image.save(&path).map_err(|e| format!("Failed to save {}: {}", path.display(), e))?;
+                Ok(path)
+            }
+            Frame::Hdr { width, height, ref pixels } => {
+                let path = path.with_extension("hdr");
+                let file = File::create(&path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+                HDREncoder::new(BufWriter::new(file))
+                    .encode(pixels, width as usize, height as usize)
+                    .map_err(|e| format!("Failed to save {}: {}", path.display(), e))?;
+                Ok(path)
+            }
+        }
+    }
+}
+
+/// Reads back the default framebuffer (the back buffer, i.e. the frame that is about to be presented)
+pub unsafe fn read_default_framebuffer(width: i32, height: i32) -> RgbaImage {
+    let mut previousReadFramebuffer = 0;
+    gl::GetIntegerv(gl::READ_FRAMEBUFFER_BINDING, &mut previousReadFramebuffer);
+    gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+    gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+
+    let mut data = vec![0u8; width as usize * height as usize * 4];
+    gl::ReadPixels(0, 0, width, height, gl::RGBA, gl::UNSIGNED_BYTE, data.as_mut_ptr() as *mut c_void);
+    gl::BindFramebuffer(gl::READ_FRAMEBUFFER, previousReadFramebuffer as u32);
+
+    // the alpha channel of the default framebuffer isn't visible on screen, so ignore it
+    for alpha in data.iter_mut().skip(3).step_by(4) {
+        *alpha = 255;
+    }
+    // OpenGL's origin is the bottom left corner
+    let image = RgbaImage::from_raw(width as u32, height as u32, data).unwrap();
+    image::imageops::flip_vertical(&image)
+}
+
+/// Reads back a registered framebuffer attachment, as HDR if it has a floating point format.
+/// Returns None if there's no framebuffer with that name.
+pub unsafe fn read_named_framebuffer(name: &str) -> Option<Frame> {
+    let (fbo, attachment) = FRAMEBUFFERS.with(|framebuffers| {
+        framebuffers.borrow().iter().find(|f| f.name == name).map(|f| (f.fbo, f.attachment))
+    })?;
+
+    let (mut previousReadFramebuffer, mut previousReadBuffer) = (0, 0);
+    gl::GetIntegerv(gl::READ_FRAMEBUFFER_BINDING, &mut previousReadFramebuffer);
+    gl::BindFramebuffer(gl::READ_FRAMEBUFFER, fbo);
+    gl::GetIntegerv(gl::READ_BUFFER, &mut previousReadBuffer);
+    gl::ReadBuffer(attachment);
+    gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+
+    let (width, height) = attachment_size(attachment);
+    let mut componentType = 0;
+    gl::GetFramebufferAttachmentParameteriv(gl::READ_FRAMEBUFFER, attachment,
+                                            gl::FRAMEBUFFER_ATTACHMENT_COMPONENT_TYPE, &mut componentType);
+
+    let frame = if componentType as GLenum == gl::FLOAT {
+        let mut data = vec![0f32; width as usize * height as usize * 3];
+        gl::ReadPixels(0, 0, width, height, gl::RGB, gl::FLOAT, data.as_mut_ptr() as *mut c_void);
+        // flip vertically while converting, OpenGL's origin is the bottom left corner
+        let pixels = data.chunks(width as usize * 3).rev()
+            .flat_map(|row| row.chunks(3).map(|p| Rgb([p[0], p[1], p[2]])))
+            .collect();
+        Frame::Hdr { width: width as u32, height: height as u32, pixels }
+    } else {
+        let mut data = vec![0u8; width as usize * height as usize * 4];
+        gl::ReadPixels(0, 0, width, height, gl::RGBA, gl::UNSIGNED_BYTE, data.as_mut_ptr() as *mut c_void);
+        let image = RgbaImage::from_raw(width as u32, height as u32, data).unwrap();
+        Frame::Ldr(image::imageops::flip_vertical(&image))
+    };
+
+    gl::ReadBuffer(previousReadBuffer as GLenum);
+    gl::BindFramebuffer(gl::READ_FRAMEBUFFER, previousReadFramebuffer as u32);
+    Some(frame)
+}
+
+/// Size of a color attachment of the currently bound read framebuffer (texture or renderbuffer)
+unsafe fn attachment_size(attachment: GLenum) -> (i32, i32) {
+    let (mut objectType, mut name) = (0, 0);
+    gl::GetFramebufferAttachmentParameteriv(gl::READ_FRAMEBUFFER, attachment,
+                                            gl::FRAMEBUFFER_ATTACHMENT_OBJECT_TYPE, &mut objectType);
+    gl::GetFramebufferAttachmentParameteriv(gl::READ_FRAMEBUFFER, attachment,
+                                            gl::FRAMEBUFFER_ATTACHMENT_OBJECT_NAME, &mut name);
+    let (mut width, mut height) = (0, 0);
+    if objectType as GLenum == gl::RENDERBUFFER {
+        let mut previous = 0;
+        gl::GetIntegerv(gl::RENDERBUFFER_BINDING, &mut previous);
+        gl::BindRenderbuffer(gl::RENDERBUFFER, name as u32);
+        gl::GetRenderbufferParameteriv(gl::RENDERBUFFER, gl::RENDERBUFFER_WIDTH, &mut width);
+        gl::GetRenderbufferParameteriv(gl::RENDERBUFFER, gl::RENDERBUFFER_HEIGHT, &mut height);
+        gl::BindRenderbuffer(gl::RENDERBUFFER, previous as u32);
+    } else {
+        let mut previous = 0;
+        gl::GetIntegerv(gl::TEXTURE_BINDING_2D, &mut previous);
+        gl::BindTexture(gl::TEXTURE_2D, name as u32);
+        gl::GetTexLevelParameteriv(gl::TEXTURE_2D, 0, gl::TEXTURE_WIDTH, &mut width);
+        gl::GetTexLevelParameteriv(gl::TEXTURE_2D, 0, gl::TEXTURE_HEIGHT, &mut height);
+        gl::BindTexture(gl::TEXTURE_2D, previous as u32);
+    }
+    (width, height)
+}
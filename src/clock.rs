@@ -0,0 +1,56 @@
+/// Time sources for the render loop. Tutorials query the time via `glfw.get_time()` (see context.rs),
+/// which uses the clock selected on the command line, so screenshots, recordings and golden tests
+/// are reproducible frame-for-frame with a fixed or scripted clock.
+
+use std::fs;
+
+#[derive(Clone, Debug)]
+pub enum Clock {
+    /// wall clock time, as in the original tutorials
+    Real,
+    /// advances by a fixed number of seconds per frame (`--fixed-dt`)
+    FixedStep(f64),
+    /// explicit time (in seconds) for each frame, e.g. loaded with `--time-script`;
+    /// after the last entry time continues with the last step
+    Scripted(Vec<f64>),
+}
+
+impl Clock {
+    /// Loads a scripted clock from a file with one time value (in seconds) per frame,
+    /// separated by whitespace. Lines starting with `#` are ignored.
+    pub fn from_script(path: &str) -> Result<Clock, String> {
+        let script = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let mut times = Vec::new();
+        for (i, line) in script.lines().enumerate() {
+            if line.trim_start().starts_with('#') {
+                continue;
+            }
+            for value in line.split_whitespace() {
+                let time = value.parse().map_err(|_| format!("{}:{}: invalid time `{}`", path, i + 1, value))?;
+                times.push(time);
+            }
+        }
+        if times.is_empty() {
+            return Err(format!("{} doesn't contain any time values", path));
+        }
+        Ok(Clock::Scripted(times))
+    }
+
+    /// Time in seconds at the given frame. Returns None for `Clock::Real`, whose time
+    /// has to be queried from the windowing system.
+    pub fn time_at(&self, frame: u64) -> Option<f64> {
+        match *self {
+            Clock::Real => None,
+            Clock::FixedStep(dt) => Some(frame as f64 * dt),
+            Clock::Scripted(ref times) => {
+                let frame = frame as usize;
+                if frame < times.len() {
+                    return Some(times[frame]);
+                }
+                let last = times[times.len() - 1];
+                let step = if times.len() > 1 { last - times[times.len() - 2] } else { 0.0 };
+                Some(last + (frame - times.len() + 1) as f64 * step)
+            }
+        }
+    }
+}
@@ -3,6 +3,7 @@
 
 /// Common code that the original tutorials repeat over and over and over and over
 
+use std::cell::Cell;
 use std::os::raw::c_void;
 use std::path::Path;
 use std::sync::mpsc::Receiver;
@@ -11,16 +12,26 @@ use gl;
 
 extern crate glfw;
 
-use self::glfw::{Key, Action};
-
 use image;
 use image::{GenericImage, DynamicImage};
 use image::DynamicImage::*;
 use image::GenericImageView;
 
+use bookmarks;
 use camera::Camera;
+use camera_path;
+use input;
+use context::Window;
 use camera::Camera_Movement::*;
 
+/// Mouse movement in pixels per second that a gamepad stick pushed all the way corresponds to
+const GAMEPAD_LOOK_SPEED: f32 = 1000.0;
+
+thread_local! {
+    /// whether `next_camera_mode` was already pressed in the last frame
+    static NEXT_MODE_PRESSED: Cell<bool> = Cell::new(false);
+}
+
 /// Event processing function as introduced in 1.7.4 (Camera Class) and used in
 /// most later tutorials
 pub fn process_events(events: &Receiver<(f64, glfw::WindowEvent)>,
@@ -49,7 +60,11 @@ pub fn process_events(events: &Receiver<(f64, glfw::WindowEvent)>,
                 *lastX = xpos;
                 *lastY = ypos;
 
-                camera.ProcessMouseMovement(xoffset, yoffset, true);
+                if camera.Panning {
+                    camera.ProcessMousePan(xoffset, yoffset);
+                } else {
+                    camera.ProcessMouseMovement(xoffset, yoffset, true);
+                }
             }
             glfw::WindowEvent::Scroll(_xoffset, yoffset) => {
                 camera.ProcessMouseScroll(yoffset as f32);
@@ -60,24 +75,60 @@ pub fn process_events(events: &Receiver<(f64, glfw::WindowEvent)>,
 }
 
 /// Input processing function as introduced in 1.7.4 (Camera Class) and used in
-/// most later tutorials
-pub fn processInput(window: &mut glfw::Window, deltaTime: f32, camera: &mut Camera) {
-    if window.get_key(Key::Escape) == Action::Press {
-        window.set_should_close(true)
+/// most later tutorials. Keys and gamepad buttons/axes are looked up by action (see input.rs).
+pub fn processInput(window: &mut Window, deltaTime: f32, camera: &mut Camera) {
+    // up/down and roll are only used by the free (6-DOF) camera mode
+    for &(action, direction) in &[("up", UP), ("down", DOWN), ("roll_left", ROLL_LEFT), ("roll_right", ROLL_RIGHT)] {
+        let value = input::value(window, action);
+        if value > 0.0 {
+            camera.ProcessKeyboard(direction, deltaTime * value);
+        }
     }
 
-    if window.get_key(Key::W) == Action::Press {
-        camera.ProcessKeyboard(FORWARD, deltaTime);
+    processCameraInput(window, deltaTime, camera);
+}
+
+/// Quitting, moving, looking around with the gamepad, camera modes, bookmarks and camera paths; the part of
+/// `processInput` that the tutorials with their own version (using some of the other keys) share.
+/// Analog bindings like the gamepad sticks move the camera proportionally slower. Camera path
+/// playback comes last, so it overrides any other camera movement.
+pub fn processCameraInput(window: &mut Window, deltaTime: f32, camera: &mut Camera) {
+    bookmarks::update(window, camera);
+
+    if input::pressed(window, "quit") {
+        window.set_should_close(true)
     }
-    if window.get_key(Key::S) == Action::Press {
-        camera.ProcessKeyboard(BACKWARD, deltaTime);
+
+    for &(action, direction) in &[("forward", FORWARD), ("backward", BACKWARD), ("left", LEFT), ("right", RIGHT)] {
+        let value = input::value(window, action);
+        if value > 0.0 {
+            camera.ProcessKeyboard(direction, deltaTime * value);
+        }
     }
-    if window.get_key(Key::A) == Action::Press {
-        camera.ProcessKeyboard(LEFT, deltaTime);
+
+    // switches between the camera modes (see `CameraMode`); while `pan` is held, looking around pans instead
+    let nextMode = input::pressed(window, "next_camera_mode");
+    if nextMode && !NEXT_MODE_PRESSED.with(Cell::get) {
+        camera.NextMode();
+        println!("Camera mode: {:?}", camera.Mode);
     }
-    if window.get_key(Key::D) == Action::Press {
-        camera.ProcessKeyboard(RIGHT, deltaTime);
+    NEXT_MODE_PRESSED.with(|pressed| pressed.set(nextMode));
+    camera.Panning = input::pressed(window, "pan");
+
+    // the sticks turn the camera like a mouse moved by GAMEPAD_LOOK_SPEED pixels per second
+    let xoffset = input::value(window, "look_right") - input::value(window, "look_left");
+    let yoffset = input::value(window, "look_up") - input::value(window, "look_down");
+    if xoffset != 0.0 || yoffset != 0.0 {
+        let (xoffset, yoffset) = (xoffset * GAMEPAD_LOOK_SPEED * deltaTime, yoffset * GAMEPAD_LOOK_SPEED * deltaTime);
+        if camera.Panning {
+            camera.ProcessMousePan(xoffset, yoffset);
+        } else {
+            camera.ProcessMouseMovement(xoffset, yoffset, true);
+        }
     }
+
+    camera.Update(deltaTime);
+    camera_path::update(window, deltaTime, camera);
 }
 
 /// utility function for loading a 2D texture from file
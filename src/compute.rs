@@ -0,0 +1,103 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+
+/// Compute programs (OpenGL 4.3 or `GL_ARB_compute_shader`) with helpers for dispatching work groups,
+/// binding shader storage buffers and images, and the memory barriers needed before the results are
+/// used elsewhere. On older contexts (e.g. the 3.3 core contexts most tutorials request) building a
+/// compute program fails with `ShaderError::Unsupported`; check `is_supported()` to pick a fallback.
+
+use std::mem::size_of;
+use std::os::raw::c_void;
+
+use gl;
+use gl::types::*;
+
+use shader::{Shader, ShaderBuilder, ShaderError};
+use utils;
+
+/// Whether the current context can run compute shaders
+pub fn is_supported() -> bool {
+    utils::gl_version() >= (4, 3) || utils::has_extension("GL_ARB_compute_shader")
+}
+
+impl Shader {
+    /// Panics with the error message if the shader can't be built, see `try_compute`
+    pub fn compute(computePath: &str) -> Shader {
+        Shader::try_compute(computePath).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_compute(computePath: &str) -> Result<Shader, ShaderError> {
+        ShaderBuilder::new().compute(computePath).build()
+    }
+
+    /// The `local_size_x/y/z` declared in the compute shader
+    pub fn work_group_size(&self) -> [u32; 3] {
+        let mut size = [0; 3];
+        unsafe { gl::GetProgramiv(self.ID, gl::COMPUTE_WORK_GROUP_SIZE, size.as_mut_ptr()); }
+        [size[0] as u32, size[1] as u32, size[2] as u32]
+    }
+
+    /// Activates the program and dispatches the given number of work groups
+    pub unsafe fn dispatch(&self, groupsX: u32, groupsY: u32, groupsZ: u32) {
+        self.useProgram();
+        gl::DispatchCompute(groupsX, groupsY, groupsZ);
+    }
+
+    /// Dispatches enough work groups to run (at least) one invocation per element of a
+    /// width x height x depth grid, e.g. per pixel of an image. Shaders have to skip invocations
+    /// outside of the grid if the size isn't a multiple of the work group size.
+    pub unsafe fn dispatch_size(&self, width: u32, height: u32, depth: u32) {
+        let size = self.work_group_size();
+        let groups = |n: u32, size: u32| (n + size - 1) / size.max(1);
+        self.dispatch(groups(width, size[0]), groups(height, size[1]), groups(depth, size[2]));
+    }
+}
+
+/// Creates a shader storage buffer with the given initial data
+pub unsafe fn create_storage_buffer<T>(data: &[T], usage: GLenum) -> u32 {
+    let mut buffer = 0;
+    gl::GenBuffers(1, &mut buffer);
+    gl::BindBuffer(gl::SHADER_STORAGE_BUFFER, buffer);
+    gl::BufferData(gl::SHADER_STORAGE_BUFFER, (data.len() * size_of::<T>()) as GLsizeiptr,
+                   data.as_ptr() as *const c_void, usage);
+    gl::BindBuffer(gl::SHADER_STORAGE_BUFFER, 0);
+    buffer
+}
+
+/// Binds a buffer to `layout(std430, binding = <binding>) buffer ...`
+pub unsafe fn bind_storage_buffer(binding: u32, buffer: u32) {
+    gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, binding, buffer);
+}
+
+/// Binds level `level` of a texture to `layout(<format>, binding = <unit>) uniform image2D ...`.
+/// `access` is `gl::READ_ONLY`, `gl::WRITE_ONLY` or `gl::READ_WRITE`; `format` must match the
+/// format in the shader, e.g. `gl::RGBA16F`. Layered textures (cube maps, arrays) are bound completely.
+pub unsafe fn bind_image(unit: u32, texture: u32, level: i32, access: GLenum, format: GLenum) {
+    gl::BindImageTexture(unit, texture, level, gl::TRUE, 0, access, format);
+}
+
+/// Makes writes to shader storage buffers visible to later shader invocations
+pub unsafe fn storage_barrier() {
+    gl::MemoryBarrier(gl::SHADER_STORAGE_BARRIER_BIT);
+}
+
+/// Makes image stores visible to later image loads
+pub unsafe fn image_barrier() {
+    gl::MemoryBarrier(gl::SHADER_IMAGE_ACCESS_BARRIER_BIT);
+}
+
+/// Makes image stores visible when the texture is sampled afterwards (e.g. a prefiltered environment map)
+pub unsafe fn texture_fetch_barrier() {
+    gl::MemoryBarrier(gl::TEXTURE_FETCH_BARRIER_BIT);
+}
+
+/// Makes storage buffer writes visible when the buffer is used as vertex data afterwards
+/// (e.g. particle positions written by a compute shader)
+pub unsafe fn vertex_attrib_barrier() {
+    gl::MemoryBarrier(gl::VERTEX_ATTRIB_ARRAY_BARRIER_BIT);
+}
+
+/// Waits for all kinds of shader writes; simple but potentially slower than the specific barriers
+pub unsafe fn full_barrier() {
+    gl::MemoryBarrier(gl::ALL_BARRIER_BITS);
+}
@@ -0,0 +1,408 @@
+#![allow(dead_code)]
+#![allow(non_snake_case)]
+
+/// Drop-in replacement for the parts of `glfw::Glfw` and `glfw::Window` used by the tutorials.
+/// Depending on the global `Options` it either opens a regular GLFW window or creates an offscreen
+/// OpenGL context (see headless.rs), so the tutorials can also run on machines without a display.
+
+use std::cell::{Cell, RefCell};
+use std::os::raw::c_void;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::Instant;
+
+use image::RgbaImage;
+
+use bookmarks;
+use camera::CameraState;
+use camera_path;
+use camera_path::CameraPath;
+use capture;
+use clock::Clock;
+use input;
+use utils;
+
+extern crate glfw;
+
+use self::glfw::{Action, CursorMode, Key, WindowEvent, WindowHint, WindowMode};
+use self::glfw::Context as GlfwContext;
+
+#[cfg(feature = "headless")]
+use headless;
+
+/// Number of frames rendered in headless mode if no explicit `frames` option is given
+const DEFAULT_HEADLESS_FRAMES: u64 = 60;
+/// Default time step in headless mode, so animations don't depend on how fast the software renderer is
+const HEADLESS_FRAME_TIME: f64 = 1.0 / 60.0;
+
+/// Global options set from the command line before a tutorial is started
+#[derive(Clone, Default)]
+pub struct Options {
+    /// render into an offscreen context instead of a window (requires the `headless` feature)
+    pub headless: bool,
+    /// close the window after rendering this many frames
+    pub frames: Option<u64>,
+    /// time source for `get_time()`; defaults to the real time with a window and a fixed step in headless mode
+    pub clock: Option<Clock>,
+    /// read back the last frame before closing the window, see `take_last_frame`
+    pub grab_last_frame: bool,
+    /// save every frame from `capture_from` on into this directory (see capture.rs)
+    pub capture_dir: Option<PathBuf>,
+    pub capture_from: u64,
+    /// capture this registered framebuffer instead of the default framebuffer
+    pub capture_fbo: Option<String>,
+    /// id of the running tutorial, used for naming captured frames
+    pub tutorial: Option<String>,
+    /// recompile shaders when their source files change (see shader.rs)
+    pub watch_shaders: bool,
+    /// directory for cached program binaries (see program_cache.rs)
+    pub shader_cache: Option<PathBuf>,
+    /// file that K adds camera keyframes to (see camera_path.rs)
+    pub record_path: Option<PathBuf>,
+    /// camera path to play back, loaded with `--play-path`
+    pub camera_path: Option<CameraPath>,
+    /// initial view of the camera (`--camera`, see bookmarks.rs)
+    pub camera: Option<CameraState>,
+}
+
+thread_local! {
+    static OPTIONS: RefCell<Options> = RefCell::new(Options::default());
+    static LAST_FRAME: RefCell<Option<RgbaImage>> = RefCell::new(None);
+    static LAST_RENDERER: RefCell<Option<String>> = RefCell::new(None);
+}
+
+pub fn set_options(options: Options) {
+    OPTIONS.with(|o| *o.borrow_mut() = options);
+}
+
+pub fn options() -> Options {
+    OPTIONS.with(|o| o.borrow().clone())
+}
+
+/// Returns the frame grabbed by the last window that was closed with the `grab_last_frame` option
+pub fn take_last_frame() -> Option<RgbaImage> {
+    LAST_FRAME.with(|f| f.borrow_mut().take())
+}
+
+/// Renderer that drew the frame returned by `take_last_frame` (see `utils::gl_renderer`)
+pub fn last_renderer() -> Option<String> {
+    LAST_RENDERER.with(|r| r.borrow().clone())
+}
+
+/// Replacement for `glfw::init(glfw::FAIL_ON_ERRORS).unwrap()`
+pub fn init() -> Glfw {
+    let options = options();
+    let (backend, default_clock) = if options.headless {
+        (Backend::Headless, Clock::FixedStep(HEADLESS_FRAME_TIME))
+    } else {
+        (Backend::Glfw(glfw::init(glfw::FAIL_ON_ERRORS).unwrap()), Clock::Real)
+    };
+    Glfw {
+        backend,
+        hints: ContextHints::default(),
+        clock: options.clock.unwrap_or(default_clock),
+        start: Instant::now(),
+        frame: Rc::new(Cell::new(0)),
+    }
+}
+
+/// The window hints the headless backend cares about
+#[derive(Clone, Copy)]
+pub struct ContextHints {
+    pub version: (u32, u32),
+    pub samples: Option<u32>,
+    pub debug: bool,
+}
+
+impl Default for ContextHints {
+    fn default() -> Self {
+        ContextHints {
+            version: (3, 3),
+            samples: None,
+            debug: false,
+        }
+    }
+}
+
+enum Backend {
+    Glfw(glfw::Glfw),
+    Headless,
+}
+
+pub struct Glfw {
+    backend: Backend,
+    hints: ContextHints,
+    clock: Clock,
+    start: Instant,
+    /// number of frames swapped so far, shared with the window
+    frame: Rc<Cell<u64>>,
+}
+
+impl Glfw {
+    pub fn window_hint(&mut self, hint: WindowHint) {
+        match hint {
+            WindowHint::ContextVersion(major, minor) => self.hints.version = (major, minor),
+            WindowHint::Samples(samples) => self.hints.samples = samples,
+            WindowHint::OpenGlDebugContext(debug) => self.hints.debug = debug,
+            _ => {}
+        }
+        if let Backend::Glfw(ref mut glfw) = self.backend {
+            glfw.window_hint(hint);
+        }
+    }
+
+    pub fn create_window(&mut self, width: u32, height: u32, title: &str, mode: WindowMode)
+                         -> Option<(Window, Receiver<(f64, WindowEvent)>)> {
+        let options = options();
+        let max_frames = if options.headless {
+            Some(options.frames.unwrap_or(DEFAULT_HEADLESS_FRAMES))
+        } else {
+            options.frames
+        };
+
+        capture::clear_framebuffers();
+        camera_path::reset(&options);
+        bookmarks::reset(&options);
+
+        let (surface, events) = match self.backend {
+            Backend::Glfw(ref mut glfw) => {
+                let (window, events) = glfw.create_window(width, height, title, mode)?;
+                (Surface::Glfw(window), events)
+            }
+            Backend::Headless => {
+                // nothing ever sends window events in headless mode, but the tutorials expect a receiver
+                let (sender, events) = channel();
+                (Surface::Headless(Headless::new(width, height, self.hints)?, sender), events)
+            }
+        };
+
+        let window = Window {
+            surface,
+            width,
+            height,
+            frame: self.frame.clone(),
+            max_frames,
+            grab_last_frame: options.grab_last_frame,
+            capture_key_down: false,
+            options,
+            should_close: false,
+        };
+        Some((window, events))
+    }
+
+    pub fn poll_events(&mut self) {
+        if let Backend::Glfw(ref mut glfw) = self.backend {
+            glfw.poll_events();
+        }
+    }
+
+    /// Time in seconds since initialization, according to the selected clock (see clock.rs)
+    pub fn get_time(&self) -> f64 {
+        if let Some(time) = self.clock.time_at(self.frame.get()) {
+            return time;
+        }
+        match self.backend {
+            Backend::Glfw(ref glfw) => glfw.get_time(),
+            Backend::Headless => {
+                let elapsed = self.start.elapsed();
+                elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 * 1e-9
+            }
+        }
+    }
+}
+
+enum Surface {
+    Glfw(glfw::Window),
+    Headless(Headless, Sender<(f64, WindowEvent)>),
+}
+
+pub struct Window {
+    surface: Surface,
+    width: u32,
+    height: u32,
+    frame: Rc<Cell<u64>>,
+    max_frames: Option<u64>,
+    grab_last_frame: bool,
+    /// whether the screenshot key (F12) was down in the last frame
+    capture_key_down: bool,
+    options: Options,
+    should_close: bool,
+}
+
+impl Window {
+    pub fn make_current(&mut self) {
+        match self.surface {
+            Surface::Glfw(ref mut window) => window.make_current(),
+            Surface::Headless(ref headless, _) => headless.make_current(),
+        }
+    }
+
+    pub fn get_proc_address(&mut self, procname: &str) -> *const c_void {
+        match self.surface {
+            Surface::Glfw(ref mut window) => window.get_proc_address(procname) as *const c_void,
+            Surface::Headless(ref headless, _) => headless.get_proc_address(procname),
+        }
+    }
+
+    pub fn swap_buffers(&mut self) {
+        let frame = self.frame.get();
+        if self.grab_last_frame && self.max_frames == Some(frame + 1) {
+            let (width, height) = self.get_framebuffer_size();
+            let image = unsafe { capture::read_default_framebuffer(width, height) };
+            LAST_FRAME.with(|f| *f.borrow_mut() = Some(image));
+            LAST_RENDERER.with(|r| *r.borrow_mut() = Some(utils::gl_renderer()));
+        }
+
+        let capture_key_down = input::pressed(self, "screenshot");
+        let screenshot = capture_key_down && !self.capture_key_down;
+        self.capture_key_down = capture_key_down;
+        if screenshot || (self.options.capture_dir.is_some() && frame >= self.options.capture_from) {
+            self.capture(frame, screenshot);
+        }
+
+        match self.surface {
+            Surface::Glfw(ref mut window) => window.swap_buffers(),
+            Surface::Headless(ref headless, _) => headless.swap_buffers(),
+        }
+        if frame == 0 {
+            camera_path::check_supported();
+        }
+        self.frame.set(self.frame.get() + 1);
+    }
+
+    /// Saves the current frame (or the framebuffer selected with `--capture-fbo`) into the capture directory
+    fn capture(&self, frame: u64, verbose: bool) {
+        let dir = self.options.capture_dir.as_ref().map_or(Path::new(capture::DEFAULT_DIR), |dir| dir.as_path());
+        let mut name = format!("{}_{:05}", self.options.tutorial.as_ref().map_or("frame", |id| id.as_str()), frame);
+        let captured = match self.options.capture_fbo {
+            Some(ref fbo) => {
+                name = format!("{}_{}", name, fbo);
+                unsafe { capture::read_named_framebuffer(fbo) }
+            }
+            None => {
+                let (width, height) = self.get_framebuffer_size();
+                Some(capture::Frame::Ldr(unsafe { capture::read_default_framebuffer(width, height) }))
+            }
+        };
+        match captured.map(|captured| captured.save(&dir.join(name))) {
+            Some(Ok(ref path)) if verbose => println!("Saved {}", path.display()),
+            Some(Ok(_)) => {}
+            Some(Err(e)) => println!("{}", e),
+            None => println!("No framebuffer named `{}` (available: {})",
+                             self.options.capture_fbo.as_ref().unwrap(), capture::framebuffer_names().join(", ")),
+        }
+    }
+
+    pub fn should_close(&self) -> bool {
+        if self.max_frames.map_or(false, |max_frames| self.frame.get() >= max_frames) {
+            return true;
+        }
+        match self.surface {
+            Surface::Glfw(ref window) => window.should_close(),
+            Surface::Headless(..) => self.should_close,
+        }
+    }
+
+    pub fn set_should_close(&mut self, value: bool) {
+        match self.surface {
+            Surface::Glfw(ref mut window) => window.set_should_close(value),
+            Surface::Headless(..) => self.should_close = value,
+        }
+    }
+
+    /// Keys are never pressed in headless mode
+    pub fn get_key(&self, key: Key) -> Action {
+        match self.surface {
+            Surface::Glfw(ref window) => window.get_key(key),
+            Surface::Headless(..) => Action::Release,
+        }
+    }
+
+    /// Mouse buttons are never pressed in headless mode
+    pub fn get_mouse_button(&self, button: glfw::MouseButton) -> Action {
+        match self.surface {
+            Surface::Glfw(ref window) => window.get_mouse_button(button),
+            Surface::Headless(..) => Action::Release,
+        }
+    }
+
+    /// State of the first connected gamepad (a joystick GLFW has a mapping for); None in headless mode
+    pub fn get_gamepad_state(&self) -> Option<glfw::GamepadState> {
+        use self::glfw::JoystickId::*;
+        match self.surface {
+            Surface::Glfw(ref window) => {
+                let joysticks = [Joystick1, Joystick2, Joystick3, Joystick4, Joystick5, Joystick6, Joystick7, Joystick8,
+                                 Joystick9, Joystick10, Joystick11, Joystick12, Joystick13, Joystick14, Joystick15, Joystick16];
+                joysticks.iter()
+                    .map(|&id| window.glfw.get_joystick(id))
+                    .find(|joystick| joystick.is_gamepad())
+                    .and_then(|joystick| joystick.get_gamepad_state())
+            }
+            Surface::Headless(..) => None,
+        }
+    }
+
+    pub fn get_framebuffer_size(&self) -> (i32, i32) {
+        match self.surface {
+            Surface::Glfw(ref window) => window.get_framebuffer_size(),
+            Surface::Headless(..) => (self.width as i32, self.height as i32),
+        }
+    }
+
+    pub fn set_cursor_mode(&mut self, mode: CursorMode) {
+        if let Surface::Glfw(ref mut window) = self.surface {
+            window.set_cursor_mode(mode);
+        }
+    }
+
+    pub fn set_key_polling(&mut self, value: bool) {
+        if let Surface::Glfw(ref mut window) = self.surface {
+            window.set_key_polling(value);
+        }
+    }
+
+    pub fn set_framebuffer_size_polling(&mut self, value: bool) {
+        if let Surface::Glfw(ref mut window) = self.surface {
+            window.set_framebuffer_size_polling(value);
+        }
+    }
+
+    pub fn set_cursor_pos_polling(&mut self, value: bool) {
+        if let Surface::Glfw(ref mut window) = self.surface {
+            window.set_cursor_pos_polling(value);
+        }
+    }
+
+    pub fn set_scroll_polling(&mut self, value: bool) {
+        if let Surface::Glfw(ref mut window) = self.surface {
+            window.set_scroll_polling(value);
+        }
+    }
+
+    pub fn set_mouse_button_polling(&mut self, value: bool) {
+        if let Surface::Glfw(ref mut window) = self.surface {
+            window.set_mouse_button_polling(value);
+        }
+    }
+}
+
+#[cfg(feature = "headless")]
+type Headless = headless::Context;
+
+/// Stand-in so the code above compiles without the `headless` feature; `--headless` is rejected in main()
+#[cfg(not(feature = "headless"))]
+struct Headless;
+
+#[cfg(not(feature = "headless"))]
+impl Headless {
+    fn new(_width: u32, _height: u32, _hints: ContextHints) -> Option<Headless> {
+        println!("Headless rendering requires the `headless` feature");
+        None
+    }
+    fn make_current(&self) {}
+    fn get_proc_address(&self, _procname: &str) -> *const c_void {
+        ::std::ptr::null()
+    }
+    fn swap_buffers(&self) {}
+}
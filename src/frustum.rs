@@ -0,0 +1,156 @@
+#![allow(dead_code)]
+
+/// View frustum culling. The six planes are extracted from a view-projection matrix (Gribb/Hartmann),
+/// so they are in world space and work with any of the camera's projections. Tests are conservative:
+/// objects near a corner of the frustum may be reported as visible although they are not.
+
+use cgmath::{Matrix4, Point3, Vector3, Vector4};
+use cgmath::prelude::*;
+
+/// Plane with `normal · p + distance >= 0` for points on the inner side
+#[derive(Clone, Copy, Debug)]
+pub struct Plane {
+    pub normal: Vector3<f32>,
+    pub distance: f32,
+}
+
+impl Plane {
+    /// Signed distance of the point to the plane (positive on the inner side)
+    pub fn distance_to(&self, point: Point3<f32>) -> f32 {
+        self.normal.dot(point.to_vec()) + self.distance
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Frustum {
+    /// left, right, bottom, top, near, far; planes without a normal (e.g. the far plane of an infinite
+    /// projection) are left out
+    pub planes: Vec<Plane>,
+}
+
+impl Frustum {
+    /// Extracts the planes from `projection * view` (or just a projection matrix for a view space frustum)
+    pub fn from_matrix(matrix: &Matrix4<f32>) -> Frustum {
+        let row = |i: usize| Vector4::new(matrix.x[i], matrix.y[i], matrix.z[i], matrix.w[i]);
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+        // a point is inside if -w <= x, y, z <= w in clip space
+        let planes = [w + x, w - x, w + y, w - y, w + z, w - z].iter().filter_map(|plane| {
+            let normal = plane.truncate();
+            let length = normal.magnitude();
+            // the far plane of an infinite projection has no normal
+            if length < 1e-6 {
+                return None;
+            }
+            Some(Plane { normal: normal / length, distance: plane.w / length })
+        }).collect();
+        Frustum { planes }
+    }
+
+    pub fn contains_point(&self, point: Point3<f32>) -> bool {
+        self.planes.iter().all(|plane| plane.distance_to(point) >= 0.0)
+    }
+
+    pub fn intersects_sphere(&self, center: Point3<f32>, radius: f32) -> bool {
+        self.planes.iter().all(|plane| plane.distance_to(center) >= -radius)
+    }
+
+    /// Tests an axis aligned bounding box given by its minimum and maximum corner
+    pub fn intersects_aabb(&self, min: Point3<f32>, max: Point3<f32>) -> bool {
+        self.planes.iter().all(|plane| {
+            // the corner furthest along the normal is the last one to leave the inner side
+            let corner = Point3::new(
+                if plane.normal.x >= 0.0 { max.x } else { min.x },
+                if plane.normal.y >= 0.0 { max.y } else { min.y },
+                if plane.normal.z >= 0.0 { max.z } else { min.z });
+            plane.distance_to(corner) >= 0.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{ortho, perspective, Deg};
+    use camera::{Camera, Projection};
+
+    fn assert_plane(plane: &Plane, normal: [f32; 3], distance: f32) {
+        let expected = Vector3::from(normal);
+        assert!((plane.normal - expected).magnitude() < 1e-5, "{:?} != {:?}", plane.normal, expected);
+        assert!((plane.distance - distance).abs() < 1e-5, "{} != {}", plane.distance, distance);
+    }
+
+    #[test]
+    fn orthographic_planes() {
+        // a box from -2 to 2 horizontally, -1 to 1 vertically and 1 to 10 units in front of the camera
+        let frustum = Frustum::from_matrix(&ortho(-2.0, 2.0, -1.0, 1.0, 1.0, 10.0));
+        assert_eq!(frustum.planes.len(), 6);
+        assert_plane(&frustum.planes[0], [1.0, 0.0, 0.0], 2.0);
+        assert_plane(&frustum.planes[1], [-1.0, 0.0, 0.0], 2.0);
+        assert_plane(&frustum.planes[2], [0.0, 1.0, 0.0], 1.0);
+        assert_plane(&frustum.planes[3], [0.0, -1.0, 0.0], 1.0);
+        assert_plane(&frustum.planes[4], [0.0, 0.0, -1.0], -1.0);
+        assert_plane(&frustum.planes[5], [0.0, 0.0, 1.0], 10.0);
+    }
+
+    #[test]
+    fn perspective_points_and_spheres() {
+        // with a 90° field of view the side planes are at |x| = |y| = -z
+        let frustum = Frustum::from_matrix(&perspective(Deg(90.0), 1.0, 1.0, 10.0));
+        assert!(frustum.contains_point(Point3::new(0.0, 0.0, -5.0)));
+        assert!(frustum.contains_point(Point3::new(4.9, -4.9, -5.0)));
+        assert!(!frustum.contains_point(Point3::new(5.1, 0.0, -5.0)));
+        assert!(!frustum.contains_point(Point3::new(0.0, 0.0, -0.5)));
+        assert!(!frustum.contains_point(Point3::new(0.0, 0.0, -10.5)));
+        assert!(!frustum.contains_point(Point3::new(0.0, 0.0, 5.0)));
+
+        // 1 / sqrt(2) outside of the right plane
+        let center = Point3::new(6.0, 0.0, -5.0);
+        assert!(frustum.intersects_sphere(center, 1.0));
+        assert!(!frustum.intersects_sphere(center, 0.5));
+        // in front of the near plane / behind the far plane
+        assert!(frustum.intersects_sphere(Point3::new(0.0, 0.0, 0.0), 1.5));
+        assert!(!frustum.intersects_sphere(Point3::new(0.0, 0.0, -12.0), 1.5));
+    }
+
+    #[test]
+    fn aabb_tests_are_conservative() {
+        let frustum = Frustum::from_matrix(&perspective(Deg(90.0), 1.0, 1.0, 10.0));
+        // completely inside, straddling the right plane, completely outside
+        assert!(frustum.intersects_aabb(Point3::new(-1.0, -1.0, -6.0), Point3::new(1.0, 1.0, -4.0)));
+        assert!(frustum.intersects_aabb(Point3::new(4.0, -1.0, -6.0), Point3::new(6.0, 1.0, -4.0)));
+        assert!(!frustum.intersects_aabb(Point3::new(7.0, -1.0, -6.0), Point3::new(8.0, 1.0, -4.0)));
+        assert!(!frustum.intersects_aabb(Point3::new(-1.0, -1.0, 1.0), Point3::new(1.0, 1.0, 2.0)));
+        // just beyond the far right edge (at x = 10, z = -10), but each plane on its own still has a
+        // corner of the box on its inner side
+        assert!(frustum.intersects_aabb(Point3::new(10.1, -1.0, -10.5), Point3::new(11.0, 1.0, -9.9)));
+    }
+
+    #[test]
+    fn infinite_far_plane_is_left_out() {
+        // OpenGL's infinite perspective projection (far -> infinity) with near = 1
+        let infinite = Matrix4::new(
+            1.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0,
+            0.0, 0.0, -1.0, -1.0,
+            0.0, 0.0, -2.0, 0.0);
+        let frustum = Frustum::from_matrix(&infinite);
+        assert_eq!(frustum.planes.len(), 5);
+        assert!(frustum.contains_point(Point3::new(0.0, 0.0, -1e6)));
+        assert!(!frustum.contains_point(Point3::new(0.0, 0.0, -0.5)));
+    }
+
+    #[test]
+    fn reversed_z_camera_frustum() {
+        let camera = Camera {
+            Position: Point3::new(0.0, 0.0, 5.0),
+            Projection: Projection::ReversedZ { near: 0.1 },
+            ..Camera::default()
+        };
+        let frustum = camera.GetFrustum(1.0);
+        assert!(frustum.contains_point(Point3::new(0.0, 0.0, -1e5)));
+        assert!(frustum.contains_point(Point3::new(0.0, 0.0, 4.8)));
+        assert!(!frustum.contains_point(Point3::new(0.0, 0.0, 4.95)));
+        assert!(!frustum.contains_point(Point3::new(0.0, 0.0, 6.0)));
+        assert!(!frustum.contains_point(Point3::new(100.0, 0.0, 0.0)));
+    }
+}
@@ -0,0 +1,219 @@
+//! Golden-image regression tests: renders a fixed frame of every tutorial offscreen and compares it
+//! against the reference PNGs in `tests/golden/`. Run with `cargo run --features headless -- golden`
+//! (or `cargo test --features headless`), optionally followed by tutorial ids to only check those.
+//! Pass `--update` to (re-)generate the references instead. Tutorials without a reference are skipped.
+//!
+//! The references depend on the rasterizer, so `tests/golden/RENDERER` records the OpenGL renderer
+//! and version they were generated with (Mesa's llvmpipe); other renderers may need a higher tolerance.
+//!
+//! Tutorials are deterministic in headless mode: there is no input, so the camera stays where the
+//! tutorial puts it, and time advances by a fixed step per frame.
+
+use std::fs;
+use std::io::{self, Write};
+use std::panic;
+use std::path::{Path, PathBuf};
+
+use image;
+use image::{Rgba, RgbaImage};
+
+use context;
+use registry;
+
+const REFERENCE_DIR: &str = "tests/golden";
+const OUTPUT_DIR: &str = "target/golden";
+/// Renderer the references were generated with, see `utils::gl_renderer`
+const RENDERER_FILE: &str = "tests/golden/RENDERER";
+
+/// Frame that is compared if no `--frames` option is given
+const DEFAULT_FRAME: u64 = 5;
+/// Maximum difference per color channel for a pixel to still count as equal
+const DEFAULT_CHANNEL_TOLERANCE: u8 = 8;
+/// Maximum percentage of differing pixels for an image to still count as equal
+const DEFAULT_MAX_DIFFERING_PERCENT: f64 = 0.5;
+
+struct Settings {
+    update: bool,
+    channel_tolerance: u8,
+    max_differing_percent: f64,
+    ids: Vec<String>,
+}
+
+/// Runs the golden tests for the given arguments (everything after `golden`) and
+/// returns whether all of them passed.
+pub fn run(args: &[String]) -> bool {
+    let settings = match parse_args(args) {
+        Some(settings) => settings,
+        None => {
+            println!("Usage: golden [--update] [--tolerance <channel delta>] [--max-diff <percent>] [ids...]");
+            return false;
+        }
+    };
+    for id in &settings.ids {
+        if registry::find(id).is_none() {
+            println!("Unknown tutorial id `{}`", id);
+            return false;
+        }
+    }
+
+    let mut options = context::options();
+    options.headless = true;
+    options.frames = Some(options.frames.unwrap_or(DEFAULT_FRAME));
+    options.grab_last_frame = true;
+    context::set_options(options);
+
+    fs::create_dir_all(OUTPUT_DIR).expect("Failed to create output directory");
+    if settings.update {
+        fs::create_dir_all(REFERENCE_DIR).expect("Failed to create reference directory");
+    }
+
+    let mut failures = Vec::new();
+    let mut skipped = Vec::new();
+    let mut renderer = None;
+    for (_, tutorial) in registry::tutorials() {
+        if !settings.ids.is_empty() && !settings.ids.iter().any(|id| id == tutorial.id) {
+            continue;
+        }
+        print!("{:10} ", tutorial.id);
+        io::stdout().flush().unwrap();
+
+        let reference = reference_path(tutorial.id);
+        if !settings.update && !reference.is_file() {
+            println!("skipped (no reference {})", reference.display());
+            skipped.push(tutorial.id);
+            continue;
+        }
+
+        // keep going if a single tutorial panics (e.g. because of a missing resource)
+        let _ = context::take_last_frame();
+        if panic::catch_unwind(tutorial.run).is_err() {
+            println!("FAILED (panicked)");
+            failures.push(tutorial.id);
+            continue;
+        }
+        let actual = match context::take_last_frame() {
+            Some(actual) => actual,
+            None => {
+                println!("FAILED (no frame rendered)");
+                failures.push(tutorial.id);
+                continue;
+            }
+        };
+        renderer = context::last_renderer();
+
+        if settings.update {
+            actual.save(&reference).expect("Failed to save reference image");
+            println!("updated {}", reference.display());
+            continue;
+        }
+
+        match compare(&actual, &reference, &settings) {
+            Ok(()) => println!("ok"),
+            Err(message) => {
+                println!("FAILED ({})", message);
+                failures.push(tutorial.id);
+            }
+        }
+    }
+
+    if let Some(renderer) = renderer {
+        if settings.update {
+            fs::write(RENDERER_FILE, format!("{}\n", renderer)).expect("Failed to save renderer");
+        } else {
+            let expected = fs::read_to_string(RENDERER_FILE).unwrap_or_default();
+            if expected.trim() != renderer {
+                println!("NOTE: the references were generated with `{}`, this is `{}`", expected.trim(), renderer);
+            }
+        }
+    }
+    if !skipped.is_empty() {
+        println!("{} tutorial(s) skipped without reference: {}", skipped.len(), skipped.join(", "));
+    }
+    if failures.is_empty() {
+        println!("All golden tests passed");
+        true
+    } else {
+        println!("{} golden test(s) failed: {}", failures.len(), failures.join(", "));
+        println!("Actual, expected and diff images are in {}/", OUTPUT_DIR);
+        false
+    }
+}
+
+fn parse_args(args: &[String]) -> Option<Settings> {
+    let mut settings = Settings {
+        update: false,
+        channel_tolerance: DEFAULT_CHANNEL_TOLERANCE,
+        max_differing_percent: DEFAULT_MAX_DIFFERING_PERCENT,
+        ids: Vec::new(),
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--update" => settings.update = true,
+            "--tolerance" => settings.channel_tolerance = args.next()?.parse().ok()?,
+            "--max-diff" => settings.max_differing_percent = args.next()?.parse().ok()?,
+            id if !id.starts_with("--") => settings.ids.push(id.to_string()),
+            _ => return None,
+        }
+    }
+    Some(settings)
+}
+
+fn reference_path(id: &str) -> PathBuf {
+    Path::new(REFERENCE_DIR).join(format!("{}.png", id))
+}
+
+fn output_path(id: &str, kind: &str) -> PathBuf {
+    Path::new(OUTPUT_DIR).join(format!("{}.{}.png", id, kind))
+}
+
+/// Compares `actual` with the reference image. On failure the actual, expected and diff images are
+/// written to the output directory.
+fn compare(actual: &RgbaImage, reference: &Path, settings: &Settings) -> Result<(), String> {
+    let id = reference.file_stem().unwrap().to_string_lossy().into_owned();
+    let save_actual = || actual.save(output_path(&id, "actual")).expect("Failed to save actual image");
+
+    let expected = match image::open(reference) {
+        Ok(expected) => expected.to_rgba(),
+        Err(error) => {
+            save_actual();
+            return Err(format!("failed to read reference {}: {}", reference.display(), error));
+        }
+    };
+    if expected.dimensions() != actual.dimensions() {
+        save_actual();
+        return Err(format!("size mismatch: expected {:?}, got {:?}", expected.dimensions(), actual.dimensions()));
+    }
+
+    let (diff, differing) = diff_images(actual, &expected, settings.channel_tolerance);
+    let (width, height) = actual.dimensions();
+    let differing_percent = differing as f64 * 100.0 / (width * height) as f64;
+    if differing_percent <= settings.max_differing_percent {
+        return Ok(());
+    }
+
+    save_actual();
+    expected.save(output_path(&id, "expected")).expect("Failed to save expected image");
+    diff.save(output_path(&id, "diff")).expect("Failed to save diff image");
+    Err(format!("{:.2}% of pixels differ (max. {}%)", differing_percent, settings.max_differing_percent))
+}
+
+/// Returns a diff image (differing pixels in red on a darkened grayscale version of the expected image)
+/// and the number of pixels where any channel differs by more than `tolerance`.
+fn diff_images(actual: &RgbaImage, expected: &RgbaImage, tolerance: u8) -> (RgbaImage, u32) {
+    let mut differing = 0;
+    let mut diff = RgbaImage::new(actual.width(), actual.height());
+    for (x, y, pixel) in diff.enumerate_pixels_mut() {
+        let a = actual.get_pixel(x, y).0;
+        let e = expected.get_pixel(x, y).0;
+        let exceeds = (0..3).any(|c| (a[c] as i32 - e[c] as i32).abs() > tolerance as i32);
+        *pixel = if exceeds {
+            differing += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let luma = ((e[0] as u32 * 299 + e[1] as u32 * 587 + e[2] as u32 * 114) / 1000 / 3) as u8;
+            Rgba([luma, luma, luma, 255])
+        };
+    }
+    (diff, differing)
+}
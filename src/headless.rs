@@ -0,0 +1,186 @@
+#![allow(non_upper_case_globals)]
+
+/// Offscreen OpenGL context via EGL, used by context.rs when running with `--headless`.
+/// Prefers Mesa's surfaceless platform (works without X11/Wayland, e.g. with llvmpipe on CI
+/// machines - set `LIBGL_ALWAYS_SOFTWARE=1` to force it) and falls back to the default display.
+/// Rendering goes into a pbuffer surface, which acts as the default framebuffer, so tutorials
+/// that render to an FBO and then bind framebuffer 0 again work unchanged.
+
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_void};
+use std::ptr;
+
+use context::ContextHints;
+
+type EGLBoolean = u32;
+type EGLenum = u32;
+type EGLint = i32;
+type EGLDisplay = *mut c_void;
+type EGLConfig = *mut c_void;
+type EGLContext = *mut c_void;
+type EGLSurface = *mut c_void;
+
+const EGL_FALSE: EGLBoolean = 0;
+const EGL_NONE: EGLint = 0x3038;
+const EGL_EXTENSIONS: EGLint = 0x3055;
+const EGL_ALPHA_SIZE: EGLint = 0x3021;
+const EGL_BLUE_SIZE: EGLint = 0x3022;
+const EGL_GREEN_SIZE: EGLint = 0x3023;
+const EGL_RED_SIZE: EGLint = 0x3024;
+const EGL_DEPTH_SIZE: EGLint = 0x3025;
+const EGL_STENCIL_SIZE: EGLint = 0x3026;
+const EGL_SAMPLES: EGLint = 0x3031;
+const EGL_SAMPLE_BUFFERS: EGLint = 0x3032;
+const EGL_SURFACE_TYPE: EGLint = 0x3033;
+const EGL_RENDERABLE_TYPE: EGLint = 0x3040;
+const EGL_HEIGHT: EGLint = 0x3056;
+const EGL_WIDTH: EGLint = 0x3057;
+const EGL_PBUFFER_BIT: EGLint = 0x0001;
+const EGL_OPENGL_BIT: EGLint = 0x0008;
+const EGL_OPENGL_API: EGLenum = 0x30A2;
+const EGL_CONTEXT_MAJOR_VERSION: EGLint = 0x3098;
+const EGL_CONTEXT_MINOR_VERSION: EGLint = 0x30FB;
+const EGL_CONTEXT_OPENGL_PROFILE_MASK: EGLint = 0x30FD;
+const EGL_CONTEXT_OPENGL_CORE_PROFILE_BIT: EGLint = 0x0001;
+const EGL_CONTEXT_OPENGL_DEBUG: EGLint = 0x31B0;
+const EGL_TRUE: EGLint = 1;
+const EGL_PLATFORM_SURFACELESS_MESA: EGLenum = 0x31DD;
+
+type PFNEGLGETPLATFORMDISPLAYEXTPROC =
+    extern "C" fn(platform: EGLenum, native_display: *mut c_void, attrib_list: *const EGLint) -> EGLDisplay;
+
+#[link(name = "EGL")]
+extern "C" {
+    fn eglGetError() -> EGLint;
+    fn eglGetProcAddress(procname: *const c_char) -> *const c_void;
+    fn eglGetDisplay(display_id: *mut c_void) -> EGLDisplay;
+    fn eglInitialize(dpy: EGLDisplay, major: *mut EGLint, minor: *mut EGLint) -> EGLBoolean;
+    fn eglTerminate(dpy: EGLDisplay) -> EGLBoolean;
+    fn eglQueryString(dpy: EGLDisplay, name: EGLint) -> *const c_char;
+    fn eglChooseConfig(dpy: EGLDisplay, attrib_list: *const EGLint, configs: *mut EGLConfig, config_size: EGLint,
+                       num_config: *mut EGLint) -> EGLBoolean;
+    fn eglBindAPI(api: EGLenum) -> EGLBoolean;
+    fn eglCreateContext(dpy: EGLDisplay, config: EGLConfig, share_context: EGLContext, attrib_list: *const EGLint)
+                        -> EGLContext;
+    fn eglDestroyContext(dpy: EGLDisplay, ctx: EGLContext) -> EGLBoolean;
+    fn eglCreatePbufferSurface(dpy: EGLDisplay, config: EGLConfig, attrib_list: *const EGLint) -> EGLSurface;
+    fn eglDestroySurface(dpy: EGLDisplay, surface: EGLSurface) -> EGLBoolean;
+    fn eglMakeCurrent(dpy: EGLDisplay, draw: EGLSurface, read: EGLSurface, ctx: EGLContext) -> EGLBoolean;
+    fn eglSwapBuffers(dpy: EGLDisplay, surface: EGLSurface) -> EGLBoolean;
+}
+
+pub struct Context {
+    display: EGLDisplay,
+    surface: EGLSurface,
+    context: EGLContext,
+}
+
+impl Context {
+    /// Creates the context and makes it current. Prints the reason and returns None on failure.
+    pub fn new(width: u32, height: u32, hints: ContextHints) -> Option<Context> {
+        unsafe {
+            let display = get_display();
+            if display.is_null() || eglInitialize(display, ptr::null_mut(), ptr::null_mut()) == EGL_FALSE {
+                return egl_error("eglInitialize");
+            }
+
+            let samples = hints.samples.unwrap_or(0) as EGLint;
+            let config_attribs = [
+                EGL_SURFACE_TYPE, EGL_PBUFFER_BIT,
+                EGL_RENDERABLE_TYPE, EGL_OPENGL_BIT,
+                EGL_RED_SIZE, 8,
+                EGL_GREEN_SIZE, 8,
+                EGL_BLUE_SIZE, 8,
+                EGL_ALPHA_SIZE, 8,
+                EGL_DEPTH_SIZE, 24,
+                EGL_STENCIL_SIZE, 8,
+                EGL_SAMPLE_BUFFERS, if samples > 0 { 1 } else { 0 },
+                EGL_SAMPLES, samples,
+                EGL_NONE,
+            ];
+            let mut config = ptr::null_mut();
+            let mut num_configs = 0;
+            if eglChooseConfig(display, config_attribs.as_ptr(), &mut config, 1, &mut num_configs) == EGL_FALSE
+                || num_configs == 0 {
+                eglTerminate(display);
+                return egl_error("eglChooseConfig");
+            }
+
+            let surface_attribs = [EGL_WIDTH, width as EGLint, EGL_HEIGHT, height as EGLint, EGL_NONE];
+            let surface = eglCreatePbufferSurface(display, config, surface_attribs.as_ptr());
+            if surface.is_null() {
+                eglTerminate(display);
+                return egl_error("eglCreatePbufferSurface");
+            }
+
+            eglBindAPI(EGL_OPENGL_API);
+            let (major, minor) = hints.version;
+            let context_attribs = [
+                EGL_CONTEXT_MAJOR_VERSION, major as EGLint,
+                EGL_CONTEXT_MINOR_VERSION, minor as EGLint,
+                EGL_CONTEXT_OPENGL_PROFILE_MASK, EGL_CONTEXT_OPENGL_CORE_PROFILE_BIT,
+                EGL_CONTEXT_OPENGL_DEBUG, if hints.debug { EGL_TRUE } else { 0 },
+                EGL_NONE,
+            ];
+            let context = eglCreateContext(display, config, ptr::null_mut(), context_attribs.as_ptr());
+            if context.is_null() {
+                eglDestroySurface(display, surface);
+                eglTerminate(display);
+                return egl_error("eglCreateContext");
+            }
+
+            let headless = Context { display, surface, context };
+            headless.make_current();
+            Some(headless)
+        }
+    }
+
+    pub fn make_current(&self) {
+        unsafe { eglMakeCurrent(self.display, self.surface, self.surface, self.context); }
+    }
+
+    pub fn get_proc_address(&self, procname: &str) -> *const c_void {
+        let procname = CString::new(procname).unwrap();
+        unsafe { eglGetProcAddress(procname.as_ptr()) }
+    }
+
+    pub fn swap_buffers(&self) {
+        unsafe { eglSwapBuffers(self.display, self.surface); }
+    }
+}
+
+impl Drop for Context {
+    fn drop(&mut self) {
+        unsafe {
+            eglMakeCurrent(self.display, ptr::null_mut(), ptr::null_mut(), ptr::null_mut());
+            eglDestroyContext(self.display, self.context);
+            eglDestroySurface(self.display, self.surface);
+            eglTerminate(self.display);
+        }
+    }
+}
+
+/// Uses the surfaceless platform if the EGL implementation supports it, the default display otherwise
+unsafe fn get_display() -> EGLDisplay {
+    let extensions = eglQueryString(ptr::null_mut(), EGL_EXTENSIONS);
+    let has_surfaceless = !extensions.is_null()
+        && CStr::from_ptr(extensions).to_string_lossy().split(' ').any(|e| e == "EGL_MESA_platform_surfaceless");
+    if has_surfaceless {
+        let name = CString::new("eglGetPlatformDisplayEXT").unwrap();
+        let get_platform_display = eglGetProcAddress(name.as_ptr());
+        if !get_platform_display.is_null() {
+            let get_platform_display: PFNEGLGETPLATFORMDISPLAYEXTPROC = ::std::mem::transmute(get_platform_display);
+            let display = get_platform_display(EGL_PLATFORM_SURFACELESS_MESA, ptr::null_mut(), ptr::null());
+            if !display.is_null() {
+                return display;
+            }
+        }
+    }
+    eglGetDisplay(ptr::null_mut())
+}
+
+fn egl_error<T>(function: &str) -> Option<T> {
+    println!("Failed to create headless OpenGL context: {} failed (EGL error 0x{:x})", function,
+             unsafe { eglGetError() });
+    None
+}
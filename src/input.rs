@@ -0,0 +1,294 @@
+#![allow(dead_code)]
+
+/// Action mapping for the tutorials' input handling. Instead of asking for a specific key, the input
+/// code asks for a named action like `forward` or `toggle_shadows`, which can be bound to any number
+/// of keys, mouse buttons and gamepad buttons/axes. The defaults are the keys of the original
+/// tutorials plus a gamepad layout (left stick moves, right stick looks around, A toggles).
+///
+/// `--bindings <file>` loads a JSON file that replaces the bindings of the actions it lists, e.g.
+/// `{ "forward": ["W", "Up", "Gamepad:LeftY-"], "toggle_shadows": ["Space", "Mouse:Middle"] }`.
+/// Keys are written like the variants of `glfw::Key` (`W`, `Space`, `LeftControl`, `F12`, `Num1`),
+/// mouse buttons as `Mouse:Left`/`Right`/`Middle` or `Mouse:4` to `Mouse:8`, gamepad buttons as
+/// `Gamepad:A`, `Gamepad:DpadUp`, ... and gamepad axes as `Gamepad:LeftX+`/`Gamepad:RightY-`
+/// (the direction in which the axis activates the action) or `Gamepad:LeftTrigger`.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde_json;
+
+extern crate glfw;
+
+use self::glfw::{Action, GamepadAxis, GamepadButton, Key, MouseButton};
+
+use context::Window;
+
+/// Axis values below this are treated as 0, so worn sticks don't make the camera drift
+const DEAD_ZONE: f32 = 0.15;
+
+/// Default bindings of all actions used by the tutorials
+const DEFAULT_BINDINGS: &[(&str, &[&str])] = &[
+    ("quit", &["Escape", "Gamepad:Back"]),
+    // camera movement (see common::processInput)
+    ("forward", &["W", "Gamepad:LeftY-"]),
+    ("backward", &["S", "Gamepad:LeftY+"]),
+    ("left", &["A", "Gamepad:LeftX-"]),
+    ("right", &["D", "Gamepad:LeftX+"]),
+    ("up", &["Space", "Gamepad:RightBumper"]),
+    ("down", &["LeftControl", "Gamepad:LeftBumper"]),
+    ("roll_left", &["Q", "Gamepad:DpadLeft"]),
+    ("roll_right", &["E", "Gamepad:DpadRight"]),
+    ("look_left", &["Gamepad:RightX-"]),
+    ("look_right", &["Gamepad:RightX+"]),
+    ("look_up", &["Gamepad:RightY-"]),
+    ("look_down", &["Gamepad:RightY+"]),
+    // camera modes (see camera::CameraMode), panning applies to the orbit modes
+    ("next_camera_mode", &["C", "Gamepad:X"]),
+    ("pan", &["Mouse:Right", "Gamepad:B"]),
+    ("record_keyframe", &["K", "Gamepad:Y"]),
+    ("screenshot", &["F12"]),
+    // number keys restore viewpoint bookmarks, with Shift they save them (see bookmarks.rs)
+    ("save_bookmark", &["LeftShift", "RightShift"]),
+    ("bookmark_1", &["Num1"]),
+    ("bookmark_2", &["Num2"]),
+    ("bookmark_3", &["Num3"]),
+    ("bookmark_4", &["Num4"]),
+    ("bookmark_5", &["Num5"]),
+    ("bookmark_6", &["Num6"]),
+    ("bookmark_7", &["Num7"]),
+    ("bookmark_8", &["Num8"]),
+    ("bookmark_9", &["Num9"]),
+    // tutorial specific toggles and settings
+    ("toggle_blinn", &["B", "Gamepad:A"]),
+    ("toggle_gamma", &["Space", "Gamepad:A"]),
+    ("toggle_shadows", &["Space", "Gamepad:A"]),
+    ("toggle_hdr", &["Space", "Gamepad:A"]),
+    ("toggle_bloom", &["Space", "Gamepad:A"]),
+    ("toggle_wireframe", &["Space", "Gamepad:A"]),
+    ("toggle_normal_mapping", &["Space", "Gamepad:A"]),
+    ("decrease", &["Q", "Gamepad:LeftTrigger"]),
+    ("increase", &["E", "Gamepad:RightTrigger"]),
+];
+
+const KEYS: &[Key] = &[
+    Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I, Key::J, Key::K, Key::L, Key::M,
+    Key::N, Key::O, Key::P, Key::Q, Key::R, Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+    Key::Num0, Key::Num1, Key::Num2, Key::Num3, Key::Num4, Key::Num5, Key::Num6, Key::Num7, Key::Num8, Key::Num9,
+    Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6, Key::F7, Key::F8, Key::F9, Key::F10, Key::F11, Key::F12,
+    Key::Kp0, Key::Kp1, Key::Kp2, Key::Kp3, Key::Kp4, Key::Kp5, Key::Kp6, Key::Kp7, Key::Kp8, Key::Kp9,
+    Key::KpAdd, Key::KpSubtract, Key::KpMultiply, Key::KpDivide, Key::KpEnter,
+    Key::Space, Key::Escape, Key::Enter, Key::Tab, Key::Backspace, Key::Insert, Key::Delete,
+    Key::Up, Key::Down, Key::Left, Key::Right, Key::PageUp, Key::PageDown, Key::Home, Key::End,
+    Key::LeftShift, Key::RightShift, Key::LeftControl, Key::RightControl, Key::LeftAlt, Key::RightAlt,
+    Key::Minus, Key::Equal, Key::Comma, Key::Period, Key::Slash, Key::Semicolon, Key::Apostrophe,
+    Key::LeftBracket, Key::RightBracket, Key::Backslash, Key::GraveAccent,
+];
+
+const MOUSE_BUTTONS: &[(&str, MouseButton)] = &[
+    ("Left", MouseButton::Button1), ("Right", MouseButton::Button2), ("Middle", MouseButton::Button3),
+    ("4", MouseButton::Button4), ("5", MouseButton::Button5), ("6", MouseButton::Button6),
+    ("7", MouseButton::Button7), ("8", MouseButton::Button8),
+];
+
+const GAMEPAD_BUTTONS: &[(&str, GamepadButton)] = &[
+    ("A", GamepadButton::ButtonA), ("B", GamepadButton::ButtonB), ("X", GamepadButton::ButtonX),
+    ("Y", GamepadButton::ButtonY), ("LeftBumper", GamepadButton::ButtonLeftBumper),
+    ("RightBumper", GamepadButton::ButtonRightBumper), ("Back", GamepadButton::ButtonBack),
+    ("Start", GamepadButton::ButtonStart), ("Guide", GamepadButton::ButtonGuide),
+    ("LeftThumb", GamepadButton::ButtonLeftThumb), ("RightThumb", GamepadButton::ButtonRightThumb),
+    ("DpadUp", GamepadButton::ButtonDpadUp), ("DpadRight", GamepadButton::ButtonDpadRight),
+    ("DpadDown", GamepadButton::ButtonDpadDown), ("DpadLeft", GamepadButton::ButtonDpadLeft),
+];
+
+const GAMEPAD_AXES: &[(&str, GamepadAxis)] = &[
+    ("LeftX", GamepadAxis::AxisLeftX), ("LeftY", GamepadAxis::AxisLeftY),
+    ("RightX", GamepadAxis::AxisRightX), ("RightY", GamepadAxis::AxisRightY),
+    ("LeftTrigger", GamepadAxis::AxisLeftTrigger), ("RightTrigger", GamepadAxis::AxisRightTrigger),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Binding {
+    Key(Key),
+    MouseButton(MouseButton),
+    GamepadButton(GamepadButton),
+    /// axis and the direction (1 or -1) in which it activates the action
+    GamepadAxis(GamepadAxis, f32),
+    /// triggers rest at -1 and are fully pressed at 1
+    GamepadTrigger(GamepadAxis),
+}
+
+impl Binding {
+    pub fn parse(name: &str) -> Result<Binding, String> {
+        let binding = if name.starts_with("Mouse:") {
+            find(MOUSE_BUTTONS, &name[6..]).map(Binding::MouseButton)
+        } else if name.starts_with("Gamepad:") {
+            let name = &name[8..];
+            let (axis, direction) = match name.chars().last() {
+                Some('+') => (&name[..name.len() - 1], 1.0),
+                Some('-') => (&name[..name.len() - 1], -1.0),
+                _ => (name, 0.0),
+            };
+            match find(GAMEPAD_AXES, axis) {
+                Some(axis) if axis == GamepadAxis::AxisLeftTrigger || axis == GamepadAxis::AxisRightTrigger =>
+                    Some(Binding::GamepadTrigger(axis)),
+                Some(axis) if direction != 0.0 => Some(Binding::GamepadAxis(axis, direction)),
+                Some(_) => return Err(format!("`{}` needs a direction, e.g. `{}+`", name, name)),
+                None => find(GAMEPAD_BUTTONS, name).map(Binding::GamepadButton),
+            }
+        } else {
+            KEYS.iter().find(|key| format!("{:?}", key) == name).map(|&key| Binding::Key(key))
+        };
+        binding.ok_or_else(|| format!("unknown key or button `{}`", name))
+    }
+
+    /// How far the binding is activated, from 0 to 1 (keys and buttons are either 0 or 1)
+    pub fn value(&self, window: &Window, gamepad: Option<&glfw::GamepadState>) -> f32 {
+        let pressed = |action: Action| if action == Action::Release { 0.0 } else { 1.0 };
+        match *self {
+            Binding::Key(key) => pressed(window.get_key(key)),
+            Binding::MouseButton(button) => pressed(window.get_mouse_button(button)),
+            Binding::GamepadButton(button) => gamepad.map_or(0.0, |state| pressed(state.get_button_state(button))),
+            Binding::GamepadAxis(axis, direction) =>
+                gamepad.map_or(0.0, |state| apply_dead_zone(state.get_axis(axis) * direction)),
+            Binding::GamepadTrigger(axis) =>
+                gamepad.map_or(0.0, |state| apply_dead_zone((state.get_axis(axis) + 1.0) / 2.0)),
+        }
+    }
+
+    fn needs_gamepad(&self) -> bool {
+        match *self {
+            Binding::GamepadButton(_) | Binding::GamepadAxis(..) | Binding::GamepadTrigger(_) => true,
+            Binding::Key(_) | Binding::MouseButton(_) => false,
+        }
+    }
+}
+
+/// Maps an axis value to 0..1 with everything below `DEAD_ZONE` at 0, rescaled so the value
+/// still starts at 0 at the edge of the dead zone
+fn apply_dead_zone(value: f32) -> f32 {
+    ((value - DEAD_ZONE) / (1.0 - DEAD_ZONE)).max(0.0).min(1.0)
+}
+
+fn find<T: Copy>(table: &[(&str, T)], name: &str) -> Option<T> {
+    table.iter().find(|entry| entry.0 == name).map(|entry| entry.1)
+}
+
+#[derive(Clone, Debug)]
+pub struct Bindings {
+    actions: HashMap<String, Vec<Binding>>,
+}
+
+impl Default for Bindings {
+    fn default() -> Bindings {
+        let actions = DEFAULT_BINDINGS.iter().map(|&(action, names)| {
+            let bindings = names.iter().map(|name| Binding::parse(name).unwrap()).collect();
+            (action.to_string(), bindings)
+        }).collect();
+        Bindings { actions }
+    }
+}
+
+impl Bindings {
+    /// Loads the defaults with the actions listed in the file replaced
+    pub fn load(path: &Path) -> Result<Bindings, String> {
+        let json = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let file: HashMap<String, Vec<String>> = serde_json::from_str(&json)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut bindings = Bindings::default();
+        for (action, names) in file {
+            if !bindings.actions.contains_key(&action) {
+                return Err(format!("{}: unknown action `{}` (available: {})", path.display(), action,
+                                   DEFAULT_BINDINGS.iter().map(|entry| entry.0).collect::<Vec<_>>().join(", ")));
+            }
+            let parsed = names.iter().map(|name| Binding::parse(name)).collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("{}: {}: {}", path.display(), action, e))?;
+            bindings.actions.insert(action, parsed);
+        }
+        Ok(bindings)
+    }
+
+    pub fn get(&self, action: &str) -> &[Binding] {
+        self.actions.get(action).map_or(&[], |bindings| bindings.as_slice())
+    }
+}
+
+thread_local! {
+    static BINDINGS: RefCell<Bindings> = RefCell::new(Bindings::default());
+}
+
+pub fn set_bindings(bindings: Bindings) {
+    BINDINGS.with(|b| *b.borrow_mut() = bindings);
+}
+
+/// How far an action is activated, from 0 to 1 (the maximum of its bindings)
+pub fn value(window: &Window, action: &str) -> f32 {
+    BINDINGS.with(|bindings| {
+        let bindings = bindings.borrow();
+        let bindings = bindings.get(action);
+        let gamepad = if bindings.iter().any(Binding::needs_gamepad) { window.get_gamepad_state() } else { None };
+        bindings.iter().map(|binding| binding.value(window, gamepad.as_ref())).fold(0.0, f32::max)
+    })
+}
+
+/// Whether an action is activated (a key is held down or an axis is pushed more than half way)
+pub fn pressed(window: &Window, action: &str) -> bool {
+    value(window, action) >= 0.5
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_keys() {
+        assert_eq!(Binding::parse("W"), Ok(Binding::Key(Key::W)));
+        assert_eq!(Binding::parse("LeftControl"), Ok(Binding::Key(Key::LeftControl)));
+        assert_eq!(Binding::parse("Num1"), Ok(Binding::Key(Key::Num1)));
+    }
+
+    #[test]
+    fn parses_mouse_buttons() {
+        assert_eq!(Binding::parse("Mouse:Right"), Ok(Binding::MouseButton(MouseButton::Button2)));
+        assert_eq!(Binding::parse("Mouse:8"), Ok(Binding::MouseButton(MouseButton::Button8)));
+    }
+
+    #[test]
+    fn parses_gamepad_buttons_and_axes() {
+        assert_eq!(Binding::parse("Gamepad:A"), Ok(Binding::GamepadButton(GamepadButton::ButtonA)));
+        assert_eq!(Binding::parse("Gamepad:DpadUp"), Ok(Binding::GamepadButton(GamepadButton::ButtonDpadUp)));
+        assert_eq!(Binding::parse("Gamepad:LeftY-"), Ok(Binding::GamepadAxis(GamepadAxis::AxisLeftY, -1.0)));
+        assert_eq!(Binding::parse("Gamepad:RightX+"), Ok(Binding::GamepadAxis(GamepadAxis::AxisRightX, 1.0)));
+        assert_eq!(Binding::parse("Gamepad:LeftTrigger"), Ok(Binding::GamepadTrigger(GamepadAxis::AxisLeftTrigger)));
+    }
+
+    #[test]
+    fn rejects_invalid_names() {
+        assert!(Binding::parse("NoSuchKey").is_err());
+        assert!(Binding::parse("Mouse:9").is_err());
+        assert!(Binding::parse("Gamepad:Z").is_err());
+        assert!(Binding::parse("w").is_err());
+        // axes need a direction
+        assert_eq!(Binding::parse("Gamepad:LeftX"), Err("`LeftX` needs a direction, e.g. `LeftX+`".to_string()));
+    }
+
+    #[test]
+    fn default_bindings_parse() {
+        let bindings = Bindings::default();
+        for &(action, names) in DEFAULT_BINDINGS {
+            assert_eq!(bindings.get(action).len(), names.len(), "{}", action);
+        }
+        assert!(bindings.get("no_such_action").is_empty());
+    }
+
+    #[test]
+    fn dead_zone_maps_to_full_range() {
+        assert_eq!(apply_dead_zone(0.0), 0.0);
+        assert_eq!(apply_dead_zone(DEAD_ZONE), 0.0);
+        assert_eq!(apply_dead_zone(1.0), 1.0);
+        assert!((apply_dead_zone((1.0 + DEAD_ZONE) / 2.0) - 0.5).abs() < 1e-6);
+        // the opposite direction of an axis doesn't activate the action
+        assert_eq!(apply_dead_zone(-1.0), 0.0);
+    }
+}
@@ -4,145 +4,257 @@ extern crate gl;
 extern crate image;
 extern crate cgmath;
 extern crate tobj;
+extern crate gltf;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+#[cfg(feature = "check-shaders")]
+extern crate glsl;
 
 mod common;
 mod shader;
+mod preprocess;
+mod program_cache;
+mod uniform;
+mod reflect;
+mod compute;
 mod macros;
+mod bookmarks;
 mod camera;
+mod camera_path;
+mod frustum;
+mod input;
+mod animation;
 mod mesh;
 mod model;
 mod utils;
+mod registry;
+mod clock;
+mod capture;
+mod context;
+mod golden;
+mod shader_check;
+#[cfg(feature = "headless")]
+mod headless;
 
 #[cfg(feature = "chapter-1")]
 mod _1_getting_started;
 
-#[cfg(feature = "chapter-1")]
-use _1_getting_started::*;
-
 #[cfg(feature = "chapter-2")]
 mod _2_lighting;
 
-#[cfg(feature = "chapter-2")]
-use _2_lighting::*;
-
 #[cfg(feature = "chapter-3")]
 mod _3_model_loading;
 
-#[cfg(feature = "chapter-3")]
-use _3_model_loading::*;
-
 #[cfg(feature = "chapter-4")]
 mod _4_advanced_opengl;
 
-#[cfg(feature = "chapter-4")]
-use _4_advanced_opengl::*;
-
 #[cfg(feature = "chapter-5")]
 mod _5_advanced_lighting;
 
-#[cfg(feature = "chapter-5")]
-use _5_advanced_lighting::*;
-
 #[cfg(feature = "chapter-6")]
 mod _6_pbr;
 
-#[cfg(feature = "chapter-6")]
-use _6_pbr::*;
-
 #[cfg(feature = "chapter-7")]
 mod _7_in_practice;
 
-#[cfg(feature = "chapter-7")]
-use _7_in_practice::*;
+#[cfg(feature = "chapter-8")]
+mod _8_guest_articles;
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() != 2 {
-        println!("Call with the number of the tutorial, e.g. `1_1_2` for _1_2_hello_window_clear.rs");
+    let mut options = context::Options::default();
+    let mut args: Vec<String> = Vec::new();
+    let mut raw_args = std::env::args().skip(1);
+    while let Some(arg) = raw_args.next() {
+        match arg.as_str() {
+            "--headless" => options.headless = true,
+            "--frames" => {
+                let (first, end) = parse_frames(raw_args.next());
+                options.capture_from = first;
+                options.frames = Some(end);
+            }
+            "--capture" => options.capture_dir = Some(parse_option::<String>(&arg, raw_args.next()).into()),
+            "--watch-shaders" => options.watch_shaders = true,
+            "--shader-cache" => options.shader_cache = Some(parse_option::<String>(&arg, raw_args.next()).into()),
+            "--capture-fbo" => options.capture_fbo = Some(parse_option(&arg, raw_args.next())),
+            "--fixed-dt" => options.clock = Some(clock::Clock::FixedStep(parse_option(&arg, raw_args.next()))),
+            "--real-time" => options.clock = Some(clock::Clock::Real),
+            "--time-script" => {
+                let path: String = parse_option(&arg, raw_args.next());
+                options.clock = Some(clock::Clock::from_script(&path).unwrap_or_else(|e| {
+                    println!("{}", e);
+                    std::process::exit(1);
+                }));
+            }
+            "--record-path" => options.record_path = Some(parse_option::<String>(&arg, raw_args.next()).into()),
+            "--play-path" => {
+                let path: String = parse_option(&arg, raw_args.next());
+                options.camera_path = Some(camera_path::CameraPath::load(path.as_ref()).unwrap_or_else(|e| {
+                    println!("{}", e);
+                    std::process::exit(1);
+                }));
+            }
+            "--camera" => {
+                let camera: String = parse_option(&arg, raw_args.next());
+                options.camera = Some(camera.parse().unwrap_or_else(|e| {
+                    println!("{}", e);
+                    std::process::exit(1);
+                }));
+            }
+            "--bindings" => {
+                let path: String = parse_option(&arg, raw_args.next());
+                input::set_bindings(input::Bindings::load(path.as_ref()).unwrap_or_else(|e| {
+                    println!("{}", e);
+                    std::process::exit(1);
+                }));
+            }
+            _ => args.push(arg),
+        }
+    }
+    let golden = args.first().map_or(false, |arg| arg == "golden");
+    if (options.headless || golden) && !cfg!(feature = "headless") {
+        println!("Headless rendering requires the `headless` feature (`cargo run --features headless -- ...`)");
+        std::process::exit(1);
+    }
+    context::set_options(options);
+
+    if golden {
+        let passed = golden::run(&args[1..]);
+        std::process::exit(if passed { 0 } else { 1 });
+    }
+    if args.first().map_or(false, |arg| arg == "check-shaders") {
+        let passed = shader_check::run(&args[1..]);
+        std::process::exit(if passed { 0 } else { 1 });
+    }
+    if args.is_empty() || args.len() > 2 {
+        print_usage();
+        std::process::exit(1);
+    }
+
+    match args[0].as_str() {
+        "list" => list(args.get(1).map(|chapter| chapter.as_str())),
+        "describe" if args.len() == 2 => describe(&args[1]),
+        tutorial_id if args.len() == 1 => run(tutorial_id),
+        _ => {
+            print_usage();
+            std::process::exit(1);
+        }
+    }
+}
+
+fn print_usage() {
+    println!("Call with the number of the tutorial, e.g. `1_1_2` for _1_2_hello_window_clear.rs");
+    println!("Other commands:");
+    println!("  list [chapter]    list all tutorials (of a chapter)");
+    println!("  describe <id>     print details about a tutorial");
+    println!("  golden [ids...]   run the golden-image tests (see golden.rs)");
+    println!("  check-shaders [--interfaces-only] [paths...]");
+    println!("                    check the shaders without an OpenGL context (see shader_check.rs)");
+    println!("Options:");
+    println!("  --headless        render offscreen without a window (requires the `headless` feature)");
+    println!("  --frames <n>      exit after rendering n frames (default in headless mode: 60)");
+    println!("  --frames <a>..<b> exit after rendering b frames; with --capture only frames a to b-1 are saved");
+    println!("  --capture <dir>   save every frame into dir (F12 saves a single screenshot into `screenshots/`)");
+    println!("  --capture-fbo <n> capture the framebuffer the tutorial registered as n instead of the window,");
+    println!("                    as .hdr if it's a floating point buffer (e.g. `hdr` in 5_6, `bright` in 5_7)");
+    println!("  --fixed-dt <s>    advance time by s seconds per frame (default in headless mode: 1/60)");
+    println!("  --time-script <f> take the time of each frame from a file (one value in seconds per frame)");
+    println!("  --real-time       use the real time, also in headless mode");
+    println!("  --watch-shaders   reload shaders when their source files change");
+    println!("  --shader-cache <dir>");
+    println!("                    store linked program binaries in dir and reuse them on the next run");
+    println!("  --camera <x,y,z,yaw,pitch>");
+    println!("                    start with the camera at this position and orientation (see bookmarks.rs)");
+    println!("  --bindings <f>    load key, mouse and gamepad bindings from a JSON file (see input.rs)");
+    println!("  --record-path <f> add the camera pose to a camera path when K is pressed (see camera_path.rs)");
+    println!("  --play-path <f>   fly along a recorded camera path and exit at its end");
+}
+
+fn parse_option<T: std::str::FromStr>(name: &str, value: Option<String>) -> T {
+    match value.as_ref().and_then(|value| value.parse().ok()) {
+        Some(value) => value,
+        None => {
+            println!("Missing or invalid value for `{}`", name);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Parses `<n>` or a range of frames `<first>..<end>` (end exclusive)
+fn parse_frames(value: Option<String>) -> (u64, u64) {
+    let value = value.unwrap_or_default();
+    let range = match value.find("..") {
+        Some(i) => value[..i].parse().ok().and_then(|first| value[i + 2..].parse().ok().map(|end| (first, end))),
+        None => value.parse().ok().map(|end| (0, end)),
+    };
+    match range {
+        Some((first, end)) if first < end => (first, end),
+        _ => {
+            println!("Missing or invalid value for `--frames` (expected `<n>` or `<first>..<end>`)");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn run(tutorial_id: &str) {
+    match registry::find(tutorial_id) {
+        Some((_, tutorial)) => {
+            let mut options = context::options();
+            options.tutorial = Some(tutorial.id.to_string());
+            context::set_options(options);
+            (tutorial.run)()
+        }
+        None => {
+            println!("Unknown tutorial id `{}`", tutorial_id);
+            unknown_id_hint(tutorial_id);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn list(chapter: Option<&str>) {
+    let chapter = chapter.map(|chapter| chapter.parse::<u32>().unwrap_or_else(|_| {
+        println!("Invalid chapter number `{}`", chapter);
         std::process::exit(1);
+    }));
+    for c in registry::CHAPTERS.iter().filter(|c| chapter.map_or(true, |number| c.number == number)) {
+        println!("{}. {}", c.number, c.title);
+        for tutorial in c.tutorials {
+            println!("  {:10} {}", tutorial.id, tutorial.title);
+        }
+    }
+    if let Some(number) = chapter {
+        if !registry::CHAPTERS.iter().any(|c| c.number == number) {
+            println!("Chapter {} is not compiled in (enable it with `--features chapter-{}`)", number, number);
+        }
     }
-    let tutorial_id = &args[1];
-
-    match tutorial_id.as_str() {
-        #[cfg(feature = "chapter-1")] "1_1_1" => main_1_1_1(),
-        #[cfg(feature = "chapter-1")] "1_1_2" => main_1_1_2(),
-        #[cfg(feature = "chapter-1")] "1_2_1" => main_1_2_1(),
-        #[cfg(feature = "chapter-1")] "1_2_2" => main_1_2_2(),
-        #[cfg(feature = "chapter-1")] "1_2_3" => main_1_2_3(),
-        #[cfg(feature = "chapter-1")] "1_2_4" => main_1_2_4(),
-        #[cfg(feature = "chapter-1")] "1_2_5" => main_1_2_5(),
-        #[cfg(feature = "chapter-1")] "1_3_1" => main_1_3_1(),
-        #[cfg(feature = "chapter-1")] "1_3_2" => main_1_3_2(),
-        #[cfg(feature = "chapter-1")] "1_3_3" => main_1_3_3(),
-        #[cfg(feature = "chapter-1")] "1_4_1" => main_1_4_1(),
-        #[cfg(feature = "chapter-1")] "1_4_2" => main_1_4_2(),
-        #[cfg(feature = "chapter-1")] "1_4_3" => main_1_4_3(),
-        #[cfg(feature = "chapter-1")] "1_4_4" => main_1_4_4(),
-        #[cfg(feature = "chapter-1")] "1_4_5" => main_1_4_5(),
-        #[cfg(feature = "chapter-1")] "1_5_1" => main_1_5_1(),
-        #[cfg(feature = "chapter-1")] "1_5_2" => main_1_5_2(),
-        #[cfg(feature = "chapter-1")] "1_6_1" => main_1_6_1(),
-        #[cfg(feature = "chapter-1")] "1_6_2" => main_1_6_2(),
-        #[cfg(feature = "chapter-1")] "1_6_3" => main_1_6_3(),
-        #[cfg(feature = "chapter-1")] "1_7_1" => main_1_7_1(),
-        #[cfg(feature = "chapter-1")] "1_7_2" => main_1_7_2(),
-        #[cfg(feature = "chapter-1")] "1_7_3" => main_1_7_3(),
-        #[cfg(feature = "chapter-1")] "1_7_4" => main_1_7_4(),
-
-        #[cfg(feature = "chapter-2")] "2_1" => main_2_1(),
-        #[cfg(feature = "chapter-2")] "2_2_1" => main_2_2_1(),
-        #[cfg(feature = "chapter-2")] "2_2_2" => main_2_2_2(),
-        #[cfg(feature = "chapter-2")] "2_3_1" => main_2_3_1(),
-        #[cfg(feature = "chapter-2")] "2_3_2" => main_2_3_2(),
-        #[cfg(feature = "chapter-2")] "2_4_1" => main_2_4_1(),
-        #[cfg(feature = "chapter-2")] "2_4_2" => main_2_4_2(),
-        #[cfg(feature = "chapter-2")] "2_4_3" => main_2_4_3(),
-        #[cfg(feature = "chapter-2")] "2_5_1" => main_2_5_1(),
-        #[cfg(feature = "chapter-2")] "2_5_2" => main_2_5_2(),
-        #[cfg(feature = "chapter-2")] "2_5_3" => main_2_5_3(),
-        #[cfg(feature = "chapter-2")] "2_5_4" => main_2_5_4(),
-        #[cfg(feature = "chapter-2")] "2_6" => main_2_6(),
-
-        #[cfg(feature = "chapter-3")] "3_1" => main_3_1(),
-
-        #[cfg(feature = "chapter-4")] "4_1_1" => main_4_1_1(),
-        #[cfg(feature = "chapter-4")] "4_1_2" => main_4_1_2(),
-        #[cfg(feature = "chapter-4")] "4_2" => main_4_2(),
-        #[cfg(feature = "chapter-4")] "4_3_1" => main_4_3_1(),
-        #[cfg(feature = "chapter-4")] "4_3_2" => main_4_3_2(),
-        #[cfg(feature = "chapter-4")] "4_5_1" => main_4_5_1(),
-        #[cfg(feature = "chapter-4")] "4_5_2" => main_4_5_2(),
-        #[cfg(feature = "chapter-4")] "4_6_1" => main_4_6_1(),
-        #[cfg(feature = "chapter-4")] "4_6_2" => main_4_6_2(),
-        #[cfg(feature = "chapter-4")] "4_8" => main_4_8(),
-        #[cfg(feature = "chapter-4")] "4_9_1" => main_4_9_1(),
-        #[cfg(feature = "chapter-4")] "4_9_2" => main_4_9_2(),
-        #[cfg(feature = "chapter-4")] "4_9_3" => main_4_9_3(),
-        #[cfg(feature = "chapter-4")] "4_10_1" => main_4_10_1(),
-        #[cfg(feature = "chapter-4")] "4_10_2" => main_4_10_2(),
-        #[cfg(feature = "chapter-4")] "4_10_3" => main_4_10_3(),
-        #[cfg(feature = "chapter-4")] "4_11" => main_4_11(),
-
-        #[cfg(feature = "chapter-5")] "5_1" => main_5_1(),
-        #[cfg(feature = "chapter-5")] "5_2" => main_5_2(),
-        #[cfg(feature = "chapter-5")] "5_3_1_1" => main_5_3_1_1(),
-        #[cfg(feature = "chapter-5")] "5_3_1_2" => main_5_3_1_2(),
-        #[cfg(feature = "chapter-5")] "5_3_1_3" => main_5_3_1_3(),
-        #[cfg(feature = "chapter-5")] "5_3_2_1" => main_5_3_2_1(),
-        #[cfg(feature = "chapter-5")] "5_3_2_2" => main_5_3_2_2(),
-        #[cfg(feature = "chapter-5")] "5_3_3" => main_5_3_3(),
-        #[cfg(feature = "chapter-5")] "5_4" => main_5_4(),
-        #[cfg(feature = "chapter-5")] "5_5_1" => main_5_5_1(),
-        #[cfg(feature = "chapter-5")] "5_5_2" => main_5_5_2(),
-        #[cfg(feature = "chapter-5")] "5_5_3" => main_5_5_3(),
-        #[cfg(feature = "chapter-5")] "5_6" => main_5_6(),
-        #[cfg(feature = "chapter-5")] "5_7" => main_5_7(),
-
-        #[cfg(feature = "chapter-6")] "6_1_1" => main_6_1_1(),
-        #[cfg(feature = "chapter-6")] "6_1_2" => main_6_1_2(),
-
-        #[cfg(feature = "chapter-7")] "7_1" => main_7_1(),
-
-        _ => println!("Unknown tutorial id")
+}
+
+fn describe(tutorial_id: &str) {
+    match registry::find(tutorial_id) {
+        Some((chapter, tutorial)) => {
+            println!("{} {}", tutorial.id, tutorial.title);
+            println!("{}", tutorial.description);
+            println!("chapter: {}. {}", chapter.number, chapter.title);
+            println!("source:  {}/{}", chapter.dir, tutorial.file);
+        }
+        None => {
+            println!("Unknown tutorial id `{}`", tutorial_id);
+            unknown_id_hint(tutorial_id);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn unknown_id_hint(tutorial_id: &str) {
+    let suggestions = registry::suggest(tutorial_id);
+    if suggestions.is_empty() {
+        println!("Use `list` to see all available tutorials.");
+    } else {
+        println!("Did you mean:");
+        for tutorial in suggestions.iter().take(5) {
+            println!("  {:10} {}", tutorial.id, tutorial.title);
+        }
     }
 }
@@ -1,21 +1,25 @@
 #![allow(non_snake_case)]
 #![allow(dead_code)]
 
+use std::collections::HashMap;
 use std::ffi::CString;
 use std::mem::size_of;
 use std::os::raw::c_void;
 use std::ptr;
 
-use cgmath::{Vector3, Vector2};
+use cgmath::{Deg, Vector2, Vector3, Vector4};
 use cgmath::prelude::*;
 use gl;
+use gl::types::*;
 
+use animation::MAX_BONE_INFLUENCE;
 use shader::Shader;
 
 // NOTE: without repr(C) the compiler may reorder the fields or use different padding/alignment than C.
 // Depending on how you pass the data to OpenGL, this may be bad. In this case it's not strictly
 // necessary though because of the `offset!` macro used below in setupMesh()
 #[repr(C)]
+#[derive(Clone, Copy)]
 pub struct Vertex {
     // position
     pub Position: Vector3<f32>,
@@ -27,8 +31,23 @@ pub struct Vertex {
     pub Tangent: Vector3<f32>,
     // bitangent
     pub Bitangent: Vector3<f32>,
+    // bone indexes which will influence this vertex (-1 for none)
+    pub BoneIDs: [i32; MAX_BONE_INFLUENCE],
+    // weights from each bone
+    pub Weights: [f32; MAX_BONE_INFLUENCE],
 }
 
+/// Attribute location, GLSL type and `Vertex` field of the vertex attributes set up in `setupMesh`
+pub const VERTEX_ATTRIBUTES: [(GLuint, GLenum, &str); 7] = [
+    (0, gl::FLOAT_VEC3, "Position"),
+    (1, gl::FLOAT_VEC3, "Normal"),
+    (2, gl::FLOAT_VEC2, "TexCoords"),
+    (3, gl::FLOAT_VEC3, "Tangent"),
+    (4, gl::FLOAT_VEC3, "Bitangent"),
+    (5, gl::INT_VEC4, "BoneIDs"),
+    (6, gl::FLOAT_VEC4, "Weights"),
+];
+
 impl Default for Vertex {
     fn default() -> Self {
         Vertex {
@@ -37,6 +56,8 @@ impl Default for Vertex {
             TexCoords: Vector2::zero(),
             Tangent: Vector3::zero(),
             Bitangent: Vector3::zero(),
+            BoneIDs: [-1; MAX_BONE_INFLUENCE],
+            Weights: [0.0; MAX_BONE_INFLUENCE],
         }
     }
 }
@@ -48,11 +69,22 @@ pub struct Texture {
     pub path: String,
 }
 
+/// Factors of a glTF metallic-roughness material, multiplied with the corresponding textures
+#[derive(Clone, Copy, Debug)]
+pub struct Material {
+    pub baseColorFactor: Vector4<f32>,
+    pub metallicFactor: f32,
+    pub roughnessFactor: f32,
+    pub emissiveFactor: Vector3<f32>,
+}
+
 pub struct Mesh {
     /*  Mesh Data  */
     pub vertices: Vec<Vertex>,
     pub indices: Vec<u32>,
     pub textures: Vec<Texture>,
+    /// only set for glTF models
+    pub material: Option<Material>,
     pub VAO: u32,
 
     /*  Render data  */
@@ -66,6 +98,7 @@ impl Mesh {
             vertices,
             indices,
             textures,
+            material: None,
             VAO: 0,
             VBO: 0,
             EBO: 0,
@@ -83,6 +116,9 @@ impl Mesh {
         let mut specularNr = 0;
         let mut normalNr = 0;
         let mut heightNr = 0;
+        let mut metallicRoughnessNr = 0;
+        let mut aoNr = 0;
+        let mut emissiveNr = 0;
         for (i, texture) in self.textures.iter().enumerate() {
             gl::ActiveTexture(gl::TEXTURE0 + i as u32); // active proper texture unit before binding
             // retrieve texture number (the N in diffuse_textureN)
@@ -104,6 +140,18 @@ impl Mesh {
                     heightNr += 1;
                     heightNr
                 }
+                "texture_metallic_roughness" => {
+                    metallicRoughnessNr += 1;
+                    metallicRoughnessNr
+                }
+                "texture_ao" => {
+                    aoNr += 1;
+                    aoNr
+                }
+                "texture_emissive" => {
+                    emissiveNr += 1;
+                    emissiveNr
+                }
                 _ => panic!("unknown texture type")
             };
             // now set the sampler to the correct texture unit
@@ -113,6 +161,18 @@ impl Mesh {
             gl::BindTexture(gl::TEXTURE_2D, texture.id);
         }
 
+        if let Some(ref material) = self.material {
+            let location = |name: &str| {
+                let name = CString::new(name).unwrap();
+                gl::GetUniformLocation(shader.ID, name.as_ptr())
+            };
+            let (baseColor, emissive) = (material.baseColorFactor, material.emissiveFactor);
+            gl::Uniform4f(location("baseColorFactor"), baseColor.x, baseColor.y, baseColor.z, baseColor.w);
+            gl::Uniform1f(location("metallicFactor"), material.metallicFactor);
+            gl::Uniform1f(location("roughnessFactor"), material.roughnessFactor);
+            gl::Uniform3f(location("emissiveFactor"), emissive.x, emissive.y, emissive.z);
+        }
+
         // draw mesh
         gl::BindVertexArray(self.VAO);
         gl::DrawElements(gl::TRIANGLES, self.indices.len() as i32, gl::UNSIGNED_INT, ptr::null());
@@ -160,7 +220,210 @@ impl Mesh {
         // vertex bitangent
         gl::EnableVertexAttribArray(4);
         gl::VertexAttribPointer(4, 3, gl::FLOAT, gl::FALSE, size, offset_of!(Vertex, Bitangent) as *const c_void);
+        // ids (integers, so they aren't converted to floats)
+        gl::EnableVertexAttribArray(5);
+        gl::VertexAttribIPointer(5, 4, gl::INT, size, offset_of!(Vertex, BoneIDs) as *const c_void);
+        // weights
+        gl::EnableVertexAttribArray(6);
+        gl::VertexAttribPointer(6, 4, gl::FLOAT, gl::FALSE, size, offset_of!(Vertex, Weights) as *const c_void);
 
         gl::BindVertexArray(0);
     }
 }
+
+/// Computes the tangent and bitangent of every vertex from the texture coordinates of the triangles
+/// it belongs to (see the normal mapping tutorial). The tangents of all triangles sharing a vertex
+/// are summed up, so larger triangles have more influence, and then made perpendicular to the normal
+/// (Gram-Schmidt). The bitangent keeps the direction of the summed bitangents, which flips where the
+/// texture is mirrored. Expects normals and texture coordinates to be set.
+pub fn calculateTangents(vertices: &mut [Vertex], indices: &[u32]) {
+    let mut tangents = vec![Vector3::zero(); vertices.len()];
+    let mut bitangents = vec![Vector3::zero(); vertices.len()];
+    for triangle in indices.chunks(3) {
+        if triangle.len() < 3 {
+            break;
+        }
+        let (i1, i2, i3) = (triangle[0] as usize, triangle[1] as usize, triangle[2] as usize);
+        let (v1, v2, v3) = (&vertices[i1], &vertices[i2], &vertices[i3]);
+        let edge1 = v2.Position - v1.Position;
+        let edge2 = v3.Position - v1.Position;
+        let deltaUV1 = v2.TexCoords - v1.TexCoords;
+        let deltaUV2 = v3.TexCoords - v1.TexCoords;
+
+        let determinant = deltaUV1.x * deltaUV2.y - deltaUV2.x * deltaUV1.y;
+        // the texture is degenerate on this triangle
+        if determinant.abs() < 1e-12 {
+            continue;
+        }
+        let f = 1.0 / determinant;
+        let tangent = (edge1 * deltaUV2.y - edge2 * deltaUV1.y) * f;
+        let bitangent = (edge2 * deltaUV1.x - edge1 * deltaUV2.x) * f;
+        for &i in &[i1, i2, i3] {
+            tangents[i] += tangent;
+            bitangents[i] += bitangent;
+        }
+    }
+
+    for (i, vertex) in vertices.iter_mut().enumerate() {
+        let normal = if vertex.Normal.magnitude2() > 0.0 { vertex.Normal.normalize() } else { vertex.Normal };
+        // Gram-Schmidt orthogonalize
+        let mut tangent = tangents[i] - normal * normal.dot(tangents[i]);
+        if tangent.magnitude2() < 1e-12 {
+            // no usable texture coordinates, any direction perpendicular to the normal will do
+            let axis = if normal.x.abs() < 0.9 { Vector3::unit_x() } else { Vector3::unit_y() };
+            tangent = axis - normal * normal.dot(axis);
+        }
+        vertex.Tangent = tangent.normalize();
+        let handedness = if normal.cross(vertex.Tangent).dot(bitangents[i]) < 0.0 { -1.0 } else { 1.0 };
+        vertex.Bitangent = normal.cross(vertex.Tangent) * handedness;
+    }
+}
+
+/// Computes normals for a mesh that has none. Each corner of a triangle gets the area weighted
+/// average of the normals of all triangles around the same position whose normal differs by at most
+/// `creaseAngle` from its own: 0 degrees gives flat shading, 180 degrees smooths across every edge.
+/// Vertices are split where their corners end up with different normals, so new vertices and
+/// indices are returned.
+pub fn generateNormals(vertices: &[Vertex], indices: &[u32], creaseAngle: Deg<f32>) -> (Vec<Vertex>, Vec<u32>) {
+    let triangles: Vec<&[u32]> = indices.chunks(3).filter(|triangle| triangle.len() == 3).collect();
+    // the length of the cross product is twice the area of the triangle
+    let faceNormals: Vec<Vector3<f32>> = triangles.iter().map(|triangle| {
+        let (p1, p2, p3) = (vertices[triangle[0] as usize].Position, vertices[triangle[1] as usize].Position,
+                            vertices[triangle[2] as usize].Position);
+        (p2 - p1).cross(p3 - p1)
+    }).collect();
+
+    // triangles around each position; vertices that were split for different texture coordinates share it
+    let key = |vertex: &Vertex| [vertex.Position.x.to_bits(), vertex.Position.y.to_bits(), vertex.Position.z.to_bits()];
+    let mut adjacent: HashMap<[u32; 3], Vec<usize>> = HashMap::new();
+    for (t, triangle) in triangles.iter().enumerate() {
+        for &i in triangle.iter() {
+            let around = adjacent.entry(key(&vertices[i as usize])).or_insert_with(Vec::new);
+            // degenerate triangles may have several corners at the same position
+            if around.last() != Some(&t) {
+                around.push(t);
+            }
+        }
+    }
+
+    let cosCrease = creaseAngle.cos();
+    let direction = |normal: Vector3<f32>| if normal.magnitude2() > 0.0 { normal.normalize() } else { normal };
+    let mut newVertices: Vec<Vertex> = Vec::with_capacity(vertices.len());
+    let mut newIndices: Vec<u32> = Vec::with_capacity(triangles.len() * 3);
+    // reuses a vertex if another corner got the same normal
+    let mut created: HashMap<(u32, [u32; 3]), u32> = HashMap::new();
+    for (t, triangle) in triangles.iter().enumerate() {
+        let faceDirection = direction(faceNormals[t]);
+        for &i in triangle.iter() {
+            let vertex = &vertices[i as usize];
+            let mut normal = Vector3::zero();
+            for &other in &adjacent[&key(vertex)] {
+                if direction(faceNormals[other]).dot(faceDirection) >= cosCrease - 1e-6 {
+                    normal += faceNormals[other];
+                }
+            }
+            let normal = if normal.magnitude2() > 0.0 { normal.normalize() } else { faceDirection };
+
+            let normalKey = [normal.x.to_bits(), normal.y.to_bits(), normal.z.to_bits()];
+            let index = *created.entry((i, normalKey)).or_insert_with(|| {
+                newVertices.push(Vertex { Normal: normal, ..*vertex });
+                (newVertices.len() - 1) as u32
+            });
+            newIndices.push(index);
+        }
+    }
+    (newVertices, newIndices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::vec3;
+
+    fn assertClose(actual: Vector3<f32>, expected: Vector3<f32>) {
+        assert!((actual - expected).magnitude() < 1e-5, "expected {:?}, got {:?}", expected, actual);
+    }
+
+    /// unit quad in the xy plane facing +z, `mirrored` flips the u coordinate
+    fn quad(mirrored: bool) -> Vec<Vertex> {
+        [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)].iter().map(|&(x, y)| Vertex {
+            Position: vec3(x, y, 0.0),
+            Normal: Vector3::unit_z(),
+            TexCoords: Vector2::new(if mirrored { 1.0 - x } else { x }, y),
+            ..Vertex::default()
+        }).collect()
+    }
+
+    const QUAD_INDICES: [u32; 6] = [0, 1, 2, 0, 2, 3];
+
+    #[test]
+    fn tangents_follow_texture_coordinates() {
+        let mut vertices = quad(false);
+        calculateTangents(&mut vertices, &QUAD_INDICES);
+        for vertex in &vertices {
+            assertClose(vertex.Tangent, Vector3::unit_x());
+            assertClose(vertex.Bitangent, Vector3::unit_y());
+            assert!(vertex.Normal.cross(vertex.Tangent).dot(vertex.Bitangent) > 0.0);
+        }
+    }
+
+    #[test]
+    fn mirrored_texture_flips_bitangent_sign() {
+        let mut vertices = quad(true);
+        calculateTangents(&mut vertices, &QUAD_INDICES);
+        for vertex in &vertices {
+            assertClose(vertex.Tangent, -Vector3::unit_x());
+            // still points along increasing v, so the tangent space is left-handed
+            assertClose(vertex.Bitangent, Vector3::unit_y());
+            assert!(vertex.Normal.cross(vertex.Tangent).dot(vertex.Bitangent) < 0.0);
+        }
+    }
+
+    /// cube of size 1 around the origin with 8 shared vertices and outward facing triangles
+    fn cube() -> (Vec<Vertex>, Vec<u32>) {
+        let vertices = (0..8).map(|i| {
+            let coordinate = |bit| if i & bit != 0 { 0.5 } else { -0.5 };
+            Vertex { Position: vec3(coordinate(1), coordinate(2), coordinate(4)), ..Vertex::default() }
+        }).collect();
+        let faces = [[0, 4, 6, 2], [1, 3, 7, 5], [0, 1, 5, 4], [2, 6, 7, 3], [0, 2, 3, 1], [4, 5, 7, 6]];
+        let indices = faces.iter().flat_map(|f| vec![f[0], f[1], f[2], f[0], f[2], f[3]]).collect();
+        (vertices, indices)
+    }
+
+    fn assertFlatCube(creaseAngle: Deg<f32>) {
+        let (vertices, indices) = cube();
+        let (vertices, indices) = generateNormals(&vertices, &indices, creaseAngle);
+        assert_eq!(vertices.len(), 24);
+        assert_eq!(indices.len(), 36);
+        for vertex in &vertices {
+            // axis aligned and outward facing
+            assert_eq!(vertex.Normal.magnitude(), 1.0);
+            assert_eq!(vertex.Normal.dot(vertex.Position), 0.5);
+        }
+    }
+
+    #[test]
+    fn zero_crease_angle_gives_flat_cube() {
+        assertFlatCube(Deg(0.0));
+    }
+
+    #[test]
+    fn cube_edges_are_sharper_than_default_crease_angle() {
+        assertFlatCube(Deg(60.0));
+    }
+
+    #[test]
+    fn full_crease_angle_smooths_cube() {
+        let (vertices, indices) = cube();
+        let (smoothed, newIndices) = generateNormals(&vertices, &indices, Deg(180.0));
+        assert_eq!(smoothed.len(), 8);
+        assert_eq!(newIndices.len(), 36);
+        for vertex in &smoothed {
+            assert!((vertex.Normal.magnitude() - 1.0).abs() < 1e-5);
+            // points away from all three faces of the corner
+            for axis in 0..3 {
+                assert!(vertex.Normal[axis] * vertex.Position[axis] > 0.0);
+            }
+        }
+    }
+}
@@ -1,35 +1,81 @@
 #![allow(non_snake_case)]
 #![allow(dead_code)]
 
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader};
 use std::os::raw::c_void;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use cgmath::{vec2, vec3};
+use cgmath::{vec2, vec3, Deg, Matrix3, Matrix4, Vector2, Vector3, Vector4};
+use cgmath::prelude::*;
 use gl;
+use gltf;
 use image;
 use image::DynamicImage::*;
 use image::{GenericImage, DynamicImage};
 use image::GenericImageView;
 use tobj;
 
-use mesh::{Mesh, Texture, Vertex};
+use animation::{Animation, Skeleton, MAX_BONE_INFLUENCE};
+use mesh::{calculateTangents, generateNormals, Material, Mesh, Texture, Vertex};
 use shader::Shader;
 
+/// Used for OBJ meshes without normals unless another angle is passed to `Model::load_with_crease_angle`
+pub const DEFAULT_CREASE_ANGLE: Deg<f32> = Deg(60.0);
+
+/// Errors while loading a model. Missing material libraries and textures only print a warning.
+#[derive(Debug)]
+pub enum ModelError {
+    Io { path: String, error: io::Error },
+    Obj { path: String, error: tobj::LoadError },
+    Gltf { path: String, error: gltf::Error },
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ModelError::Io { ref path, ref error } => write!(f, "Failed to read {}: {}", path, error),
+            ModelError::Obj { ref path, ref error } => write!(f, "Failed to load {}: {}", path, error),
+            ModelError::Gltf { ref path, ref error } => write!(f, "Failed to load {}: {}", path, error),
+        }
+    }
+}
+
+impl Error for ModelError {}
+
 #[derive(Default)]
 pub struct Model {
     /*  Model Data */
     pub meshes: Vec<Mesh>,
     pub textures_loaded: Vec<Texture>,
     // stores all the textures loaded so far, optimization to make sure textures aren't loaded more than once.
-    directory: String,
+    directory: PathBuf,
+    /// nodes and bones of glTF models, empty for OBJ
+    pub skeleton: Skeleton,
+    pub animations: Vec<Animation>,
 }
 
 impl Model {
-    /// constructor, expects a filepath to a 3D model.
+    /// constructor, expects a filepath to a 3D model. Panics with the error message if the model
+    /// can't be loaded, see `load`
     pub fn new(path: &str) -> Model {
+        Model::load(path).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Wavefront OBJ and glTF 2.0 (.gltf and .glb) are supported, selected by the file extension.
+    pub fn load(path: &str) -> Result<Model, ModelError> {
+        Model::load_with_crease_angle(path, DEFAULT_CREASE_ANGLE)
+    }
+
+    /// Normals missing in an OBJ file are generated with smooth shading across edges where the
+    /// faces meet at no more than `creaseAngle` (see `mesh::generateNormals`).
+    pub fn load_with_crease_angle(path: &str, creaseAngle: Deg<f32>) -> Result<Model, ModelError> {
         let mut model = Model::default();
-        model.loadModel(path);
-        model
+        model.loadModel(path, creaseAngle)?;
+        Ok(model)
     }
 
     pub fn Draw(&self, shader: &Shader) {
@@ -39,31 +85,52 @@ impl Model {
     }
 
     // loads a model from file and stores the resulting meshes in the meshes vector.
-    fn loadModel(&mut self, path: &str) {
+    fn loadModel(&mut self, path: &str, creaseAngle: Deg<f32>) -> Result<(), ModelError> {
         let path = Path::new(path);
 
         // retrieve the directory path of the filepath
-        self.directory = path.parent().unwrap_or_else(|| Path::new("")).to_str().unwrap().into();
-        let obj = tobj::load_obj(path);
+        self.directory = path.parent().unwrap_or_else(|| Path::new("")).to_path_buf();
+        match path.extension().and_then(|extension| extension.to_str()).map(|extension| extension.to_lowercase()) {
+            Some(ref extension) if extension == "gltf" || extension == "glb" => return self.loadGltf(path),
+            _ => {}
+        }
+        let file = File::open(path).map_err(|error| ModelError::Io { path: path.display().to_string(), error })?;
+        let obj = tobj::load_obj_buf(&mut BufReader::new(file), |mtlPath| {
+            // a missing material library shouldn't prevent loading the geometry
+            let mtlPath = self.directory.join(mtlPath);
+            tobj::load_mtl(&mtlPath).or_else(|e| {
+                println!("WARNING::MODEL {}: failed to load material library {}: {}", path.display(), mtlPath.display(), e);
+                Ok((Vec::new(), HashMap::new()))
+            })
+        });
 
-        let (models, materials) = obj.unwrap();
+        let (models, materials) = obj.map_err(|error| ModelError::Obj { path: path.display().to_string(), error })?;
         for model in models {
             let mesh = &model.mesh;
             let num_vertices = mesh.positions.len() / 3;
 
             // data to fill
             let mut vertices: Vec<Vertex> = Vec::with_capacity(num_vertices);
-            let indices: Vec<u32> = mesh.indices.clone();
+            let mut indices: Vec<u32> = mesh.indices.clone();
 
+            // normals and texture coordinates are optional in OBJ files
             let (p, n, t) = (&mesh.positions, &mesh.normals, &mesh.texcoords);
+            let (hasNormals, hasTexCoords) = (n.len() >= num_vertices * 3, t.len() >= num_vertices * 2);
             for i in 0..num_vertices {
                 vertices.push(Vertex {
                     Position: vec3(p[i * 3], p[i * 3 + 1], p[i * 3 + 2]),
-                    Normal: vec3(n[i * 3], n[i * 3 + 1], n[i * 3 + 2]),
-                    TexCoords: vec2(t[i * 2], t[i * 2 + 1]),
+                    Normal: if hasNormals { vec3(n[i * 3], n[i * 3 + 1], n[i * 3 + 2]) } else { Vector3::zero() },
+                    TexCoords: if hasTexCoords { vec2(t[i * 2], t[i * 2 + 1]) } else { Vector2::zero() },
                     ..Vertex::default()
                 })
             }
+            if !hasNormals {
+                let (newVertices, newIndices) = generateNormals(&vertices, &indices, creaseAngle);
+                vertices = newVertices;
+                indices = newIndices;
+            }
+            // normal maps are in tangent space
+            calculateTangents(&mut vertices, &indices);
 
             // process material
             let mut textures = Vec::new();
@@ -72,51 +139,232 @@ impl Model {
 
                 // 1. diffuse map
                 if !material.diffuse_texture.is_empty() {
-                    let texture = self.loadMaterialTexture(&material.diffuse_texture, "texture_diffuse");
-                    textures.push(texture);
+                    textures.extend(self.loadMaterialTexture(&material.diffuse_texture, "texture_diffuse"));
                 }
                 // 2. specular map
                 if !material.specular_texture.is_empty() {
-                    let texture = self.loadMaterialTexture(&material.specular_texture, "texture_specular");
-                    textures.push(texture);
+                    textures.extend(self.loadMaterialTexture(&material.specular_texture, "texture_specular"));
                 }
-                // 3. normal map
-                if !material.normal_texture.is_empty() {
-                    let texture = self.loadMaterialTexture(&material.normal_texture, "texture_normal");
-                    textures.push(texture);
+                // 3. normal map (depending on the tobj version `map_Bump` is only available as an unknown parameter)
+                let normalTexture = match material.unknown_param.get("map_Bump") {
+                    Some(texture) if material.normal_texture.is_empty() => texture,
+                    _ => &material.normal_texture,
+                };
+                if !normalTexture.is_empty() {
+                    textures.extend(self.loadMaterialTexture(normalTexture, "texture_normal"));
                 }
                 // NOTE: no height maps
             }
 
             self.meshes.push(Mesh::new(vertices, indices, textures));
         }
+        Ok(())
     }
 
-    fn loadMaterialTexture(&mut self, path: &str, typeName: &str) -> Texture {
+    /// `None` (after printing a warning) if the texture can't be loaded
+    fn loadMaterialTexture(&mut self, path: &str, typeName: &str) -> Option<Texture> {
         {
             let texture = self.textures_loaded.iter().find(|t| t.path == path);
             if let Some(texture) = texture {
-                return texture.clone();
+                return Some(texture.clone());
             }
         }
 
+        let id = match unsafe { TextureFromFile(path, &self.directory) } {
+            Ok(id) => id,
+            Err(e) => {
+                println!("WARNING::MODEL failed to load texture {}: {}", self.directory.join(path).display(), e);
+                return None;
+            }
+        };
         let texture = Texture {
-            id: unsafe { TextureFromFile(path, &self.directory) },
+            id,
             type_: typeName.into(),
             path: path.into(),
         };
         self.textures_loaded.push(texture.clone());
+        Some(texture)
+    }
+
+    /// loads a glTF 2.0 model with external, embedded (base64) or binary (.glb) buffers and images.
+    /// Every triangle primitive becomes a mesh with the transforms of its node and all parent nodes applied,
+    /// except for skinned meshes, which are positioned by their bones (see animation.rs).
+    fn loadGltf(&mut self, path: &Path) -> Result<(), ModelError> {
+        let (document, buffers, images) = gltf::import(path)
+            .map_err(|error| ModelError::Gltf { path: path.display().to_string(), error })?;
+
+        // models without a default scene are expected to show the first one
+        if let Some(scene) = document.default_scene().or_else(|| document.scenes().next()) {
+            self.skeleton = Skeleton::from_gltf(&document, &scene, &buffers);
+            self.animations = Animation::from_gltf(&document, &buffers);
+            for node in scene.nodes() {
+                self.loadGltfNode(&node, Matrix4::identity(), &buffers, &images);
+            }
+        }
+        Ok(())
+    }
+
+    fn loadGltfNode(&mut self, node: &gltf::Node, parentTransform: Matrix4<f32>,
+                    buffers: &[gltf::buffer::Data], images: &[gltf::image::Data]) {
+        let transform = parentTransform * Matrix4::from(node.transform().matrix());
+        if let Some(mesh) = node.mesh() {
+            // the vertices of skinned meshes are in model space, the node transform is ignored
+            let firstBone = node.skin().map(|skin| self.skeleton.skinBones[skin.index()]);
+            let meshTransform = if firstBone.is_some() { Matrix4::identity() } else { transform };
+            for primitive in mesh.primitives() {
+                if primitive.mode() != gltf::mesh::Mode::Triangles {
+                    println!("Skipping primitive of mesh {:?} with mode {:?}", mesh.name().unwrap_or(""), primitive.mode());
+                    continue;
+                }
+                let mesh = self.loadGltfPrimitive(&primitive, &meshTransform, firstBone, buffers, images);
+                self.meshes.push(mesh);
+            }
+        }
+        for child in node.children() {
+            self.loadGltfNode(&child, transform, buffers, images);
+        }
+    }
+
+    /// `firstBone` is the index of the first joint of the node's skin in `Skeleton::bones`
+    fn loadGltfPrimitive(&mut self, primitive: &gltf::Primitive, transform: &Matrix4<f32>, firstBone: Option<usize>,
+                         buffers: &[gltf::buffer::Data], images: &[gltf::image::Data]) -> Mesh {
+        let reader = primitive.reader(|buffer| Some(&*buffers[buffer.index()]));
+
+        let positions: Vec<[f32; 3]> = reader.read_positions().map(|p| p.collect()).unwrap_or_default();
+        let normals: Option<Vec<[f32; 3]>> = reader.read_normals().map(|n| n.collect());
+        let texCoords: Option<Vec<[f32; 2]>> = reader.read_tex_coords(0).map(|t| t.into_f32().collect());
+        let tangents: Option<Vec<[f32; 4]>> = reader.read_tangents().map(|t| t.collect());
+        let joints: Option<Vec<[u16; 4]>> = reader.read_joints(0).map(|j| j.into_u16().collect());
+        let weights: Option<Vec<[f32; 4]>> = reader.read_weights(0).map(|w| w.into_f32().collect());
+
+        // normals need the inverse transpose to stay perpendicular under non-uniform scaling,
+        // tangents lie in the surface and are transformed like positions
+        let linear = Matrix3::from_cols(transform.x.truncate(), transform.y.truncate(), transform.z.truncate());
+        let normalMatrix = linear.invert().unwrap_or_else(Matrix3::identity).transpose();
+        let normalize = |v: Vector3<f32>| if v.magnitude2() > 0.0 { v.normalize() } else { v };
+
+        let mut vertices: Vec<Vertex> = Vec::with_capacity(positions.len());
+        for (i, position) in positions.iter().enumerate() {
+            let mut vertex = Vertex {
+                Position: (transform * Vector3::from(*position).extend(1.0)).truncate(),
+                ..Vertex::default()
+            };
+            if let Some(ref normals) = normals {
+                vertex.Normal = normalize(normalMatrix * Vector3::from(normals[i]));
+            }
+            if let Some(ref texCoords) = texCoords {
+                vertex.TexCoords = Vector2::from(texCoords[i]);
+            }
+            if let Some(ref tangents) = tangents {
+                let tangent = Vector4::from(tangents[i]);
+                vertex.Tangent = normalize(linear * tangent.truncate());
+                // w is the handedness of the tangent space
+                vertex.Bitangent = vertex.Normal.cross(vertex.Tangent) * tangent.w;
+            }
+            if let (Some(firstBone), Some(joints), Some(weights)) = (firstBone, joints.as_ref(), weights.as_ref()) {
+                let (boneIDs, boneWeights) = packBoneInfluences(firstBone, joints[i], weights[i]);
+                vertex.BoneIDs = boneIDs;
+                vertex.Weights = boneWeights;
+            }
+            vertices.push(vertex);
+        }
+
+        let mut indices: Vec<u32> = match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect(),
+            None => (0..vertices.len() as u32).collect(),
+        };
+        // a mirroring transform flips the winding order
+        if linear.determinant() < 0.0 {
+            for triangle in indices.chunks_mut(3) {
+                triangle.reverse();
+            }
+        }
+        // glTF requires flat normals if there are none, in which case the tangents are ignored as well
+        if normals.is_none() {
+            let (newVertices, newIndices) = generateNormals(&vertices, &indices, Deg(0.0));
+            vertices = newVertices;
+            indices = newIndices;
+        }
+        if (tangents.is_none() || normals.is_none()) && texCoords.is_some() {
+            calculateTangents(&mut vertices, &indices);
+        }
+
+        // process material
+        let material = primitive.material();
+        let pbr = material.pbr_metallic_roughness();
+        let materialTextures = [
+            (pbr.base_color_texture().map(|info| info.texture()), "texture_diffuse"),
+            (pbr.metallic_roughness_texture().map(|info| info.texture()), "texture_metallic_roughness"),
+            (material.normal_texture().map(|normal| normal.texture()), "texture_normal"),
+            (material.occlusion_texture().map(|occlusion| occlusion.texture()), "texture_ao"),
+            (material.emissive_texture().map(|info| info.texture()), "texture_emissive"),
+        ];
+        let mut textures = Vec::new();
+        for &(ref texture, typeName) in materialTextures.iter() {
+            if let Some(ref texture) = *texture {
+                textures.push(self.loadGltfTexture(texture.source().index(), images, typeName));
+            }
+        }
+
+        let mut mesh = Mesh::new(vertices, indices, textures);
+        mesh.material = Some(Material {
+            baseColorFactor: Vector4::from(pbr.base_color_factor()),
+            metallicFactor: pbr.metallic_factor(),
+            roughnessFactor: pbr.roughness_factor(),
+            emissiveFactor: Vector3::from(material.emissive_factor()),
+        });
+        mesh
+    }
+
+    /// glTF images are identified by their index, the same image may be used for several texture types.
+    /// Base color and emissive textures are sRGB encoded and uploaded as sRGB textures, so shaders
+    /// sample linear colors from them (and have to gamma correct their output).
+    fn loadGltfTexture(&mut self, index: usize, images: &[gltf::image::Data], typeName: &str) -> Texture {
+        let srgb = typeName == "texture_diffuse" || typeName == "texture_emissive";
+        let path = format!("#image{}{}", index, if srgb { " (sRGB)" } else { "" });
+        {
+            let texture = self.textures_loaded.iter().find(|t| t.path == path);
+            if let Some(texture) = texture {
+                return Texture { type_: typeName.into(), ..texture.clone() };
+            }
+        }
+
+        let texture = Texture {
+            id: unsafe { TextureFromData(&images[index], srgb) },
+            type_: typeName.into(),
+            path,
+        };
+        self.textures_loaded.push(texture.clone());
         texture
     }
 }
 
-unsafe fn TextureFromFile(path: &str, directory: &str) -> u32 {
-    let filename = format!("{}/{}", directory, path);
+/// Moves the bone influences of a vertex with a non-zero weight into the leading slots (glTF allows
+/// zero weights anywhere, but the shader treats a vertex whose first bone is -1 as unskinned) and
+/// normalizes the weights. Unused slots keep the bone id -1.
+fn packBoneInfluences(firstBone: usize, joints: [u16; 4], weights: [f32; 4]) -> ([i32; MAX_BONE_INFLUENCE], [f32; MAX_BONE_INFLUENCE]) {
+    let mut boneIDs = [-1; MAX_BONE_INFLUENCE];
+    let mut boneWeights = [0.0; MAX_BONE_INFLUENCE];
+    let total: f32 = weights.iter().sum();
+    let mut k = 0;
+    for (&joint, &weight) in joints.iter().zip(weights.iter()) {
+        if weight > 0.0 {
+            boneIDs[k] = (firstBone + joint as usize) as i32;
+            boneWeights[k] = weight / total;
+            k += 1;
+        }
+    }
+    (boneIDs, boneWeights)
+}
+
+unsafe fn TextureFromFile(path: &str, directory: &Path) -> Result<u32, image::ImageError> {
+    let filename = directory.join(path);
+
+    let img = image::open(&filename)?;
 
     let mut textureID = 0;
     gl::GenTextures(1, &mut textureID);
 
-    let img = image::open(&Path::new(&filename)).expect("Texture failed to load");
     let format = match img {
         ImageLuma8(_) => gl::RED,
         ImageLumaA8(_) => gl::RG,
@@ -138,5 +386,68 @@ unsafe fn TextureFromFile(path: &str, directory: &str) -> u32 {
     gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR_MIPMAP_LINEAR as i32);
     gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
 
+    Ok(textureID)
+}
+
+/// uploads an image decoded by the glTF importer (8 or 16 bits per channel). With `srgb`, 8 bit RGB(A)
+/// images get an sRGB internal format (OpenGL has none for the other formats, they stay linear).
+unsafe fn TextureFromData(image: &gltf::image::Data, srgb: bool) -> u32 {
+    use gltf::image::Format::*;
+
+    let mut textureID = 0;
+    gl::GenTextures(1, &mut textureID);
+
+    let (format, type_) = match image.format {
+        R8 => (gl::RED, gl::UNSIGNED_BYTE),
+        R8G8 => (gl::RG, gl::UNSIGNED_BYTE),
+        R8G8B8 => (gl::RGB, gl::UNSIGNED_BYTE),
+        R8G8B8A8 => (gl::RGBA, gl::UNSIGNED_BYTE),
+        B8G8R8 => (gl::BGR, gl::UNSIGNED_BYTE),
+        B8G8R8A8 => (gl::BGRA, gl::UNSIGNED_BYTE),
+        R16 => (gl::RED, gl::UNSIGNED_SHORT),
+        R16G16 => (gl::RG, gl::UNSIGNED_SHORT),
+        R16G16B16 => (gl::RGB, gl::UNSIGNED_SHORT),
+        R16G16B16A16 => (gl::RGBA, gl::UNSIGNED_SHORT),
+    };
+    let internalFormat = match (format, type_ == gl::UNSIGNED_BYTE && srgb) {
+        (gl::RGB, true) | (gl::BGR, true) => gl::SRGB8,
+        (gl::RGBA, true) | (gl::BGRA, true) => gl::SRGB8_ALPHA8,
+        (gl::BGR, false) => gl::RGB,
+        (gl::BGRA, false) => gl::RGBA,
+        (format, _) => format,
+    };
+
+    gl::BindTexture(gl::TEXTURE_2D, textureID);
+    // rows of RGB images with an odd width aren't 4 byte aligned
+    gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+    gl::TexImage2D(gl::TEXTURE_2D, 0, internalFormat as i32, image.width as i32, image.height as i32,
+                   0, format, type_, &image.pixels[0] as *const u8 as *const c_void);
+    gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+    gl::GenerateMipmap(gl::TEXTURE_2D);
+
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR_MIPMAP_LINEAR as i32);
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+
     textureID
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bone_influences_are_packed_into_leading_slots() {
+        let (ids, weights) = packBoneInfluences(10, [3, 1, 0, 2], [0.0, 0.5, 0.0, 1.5]);
+        assert_eq!(ids, [11, 12, -1, -1]);
+        assert_eq!(weights, [0.25, 0.75, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn full_bone_influences_keep_their_order() {
+        let (ids, weights) = packBoneInfluences(0, [0, 1, 2, 3], [0.25, 0.25, 0.25, 0.25]);
+        assert_eq!(ids, [0, 1, 2, 3]);
+        assert_eq!(weights, [0.25; 4]);
+    }
+}
@@ -0,0 +1,260 @@
+#![allow(non_snake_case)]
+
+/// GLSL preprocessing done before handing the sources to the driver (see shader.rs):
+/// resolves `#include "file"` (relative to the including file, then in the include directories),
+/// injects `#define`s after the `#version` line and emits `#line <line> <source string>` directives,
+/// so the locations in compile errors can be mapped back to the right file with `Source::files`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use shader::ShaderError;
+
+/// Directory searched for includes that aren't found next to the including file
+pub const DEFAULT_INCLUDE_DIR: &str = "src/shaders";
+
+/// A preprocessed shader stage
+pub struct Source {
+    pub code: String,
+    /// paths of all files the code was assembled from, indexed by the source string number used in `#line`
+    pub files: Vec<String>,
+}
+
+pub fn preprocess(path: &str, defines: &[(String, String)], includeDirs: &[PathBuf]) -> Result<Source, ShaderError> {
+    let mut preprocessor = Preprocessor {
+        includeDirs,
+        code: String::new(),
+        files: Vec::new(),
+        stack: Vec::new(),
+    };
+    preprocessor.process(Path::new(path), Some(defines), None)?;
+    Ok(Source { code: preprocessor.code, files: preprocessor.files })
+}
+
+struct Preprocessor<'a> {
+    includeDirs: &'a [PathBuf],
+    code: String,
+    files: Vec<String>,
+    /// canonical paths of the files currently being processed, for detecting include cycles
+    stack: Vec<PathBuf>,
+}
+
+impl<'a> Preprocessor<'a> {
+    /// Appends the processed contents of a file. `defines` is only given for the main file;
+    /// `includedAt` is the location of the `#include` directive for included files.
+    fn process(&mut self, path: &Path, defines: Option<&[(String, String)]>, includedAt: Option<(&str, usize)>)
+               -> Result<(), ShaderError> {
+        let display = path.to_string_lossy().into_owned();
+        let code = fs::read_to_string(path).map_err(|error| match includedAt {
+            Some((file, line)) => preprocess_error(file, line, format!("failed to read {}: {}", display, error)),
+            None => ShaderError::Io { path: display.clone(), error },
+        })?;
+        let canonical = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        if let Some(start) = self.stack.iter().position(|p| *p == canonical) {
+            let (file, line) = includedAt.unwrap();
+            let cycle: Vec<_> = self.stack[start..].iter().chain(Some(&canonical))
+                .map(|p| p.to_string_lossy().into_owned()).collect();
+            return Err(preprocess_error(file, line, format!("include cycle: {}", cycle.join(" -> "))));
+        }
+        self.stack.push(canonical);
+
+        let index = self.files.len();
+        self.files.push(display.clone());
+        let hasVersion = code.lines().any(|line| line.trim_start().starts_with("#version"));
+        if let Some(defines) = defines {
+            if !hasVersion {
+                self.emitDefines(defines, index, 1);
+            }
+        } else {
+            if hasVersion {
+                let line = code.lines().position(|line| line.trim_start().starts_with("#version")).unwrap() + 1;
+                return Err(preprocess_error(&display, line, "#version is not allowed in included files".into()));
+            }
+            self.code += &format!("#line 1 {}\n", index);
+        }
+
+        for (i, line) in code.lines().enumerate() {
+            let lineNumber = i + 1;
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("#version") {
+                self.code += line;
+                self.code.push('\n');
+                if let Some(defines) = defines {
+                    self.emitDefines(defines, index, lineNumber + 1);
+                }
+            } else if trimmed.starts_with("#include") {
+                let include = parse_include(trimmed)
+                    .ok_or_else(|| preprocess_error(&display, lineNumber, "expected #include \"file\"".into()))?;
+                let resolved = self.resolve(path, include)
+                    .ok_or_else(|| preprocess_error(&display, lineNumber, format!("include {} not found", include)))?;
+                self.process(&resolved, None, Some((&display, lineNumber)))?;
+                self.code += &format!("#line {} {}\n", lineNumber + 1, index);
+            } else {
+                self.code += line;
+                self.code.push('\n');
+            }
+        }
+
+        self.stack.pop();
+        Ok(())
+    }
+
+    /// Writes the defines, followed by a `#line` directive to continue at `nextLine`
+    fn emitDefines(&mut self, defines: &[(String, String)], index: usize, nextLine: usize) {
+        for &(ref name, ref value) in defines {
+            self.code += &format!("#define {} {}", name, value).trim_end();
+            self.code.push('\n');
+        }
+        self.code += &format!("#line {} {}\n", nextLine, index);
+    }
+
+    fn resolve(&self, includingFile: &Path, include: &str) -> Option<PathBuf> {
+        let relative = includingFile.parent().map(|dir| dir.join(include));
+        relative.into_iter()
+            .chain(self.includeDirs.iter().map(|dir| dir.join(include)))
+            .find(|path| path.is_file())
+    }
+}
+
+/// Returns the file name of `#include "file"` or `#include <file>`
+fn parse_include(line: &str) -> Option<&str> {
+    let rest = line["#include".len()..].trim();
+    let close = match rest.chars().next()? {
+        '"' => '"',
+        '<' => '>',
+        _ => return None,
+    };
+    let end = rest[1..].find(close)?;
+    Some(&rest[1..1 + end])
+}
+
+fn preprocess_error(path: &str, line: usize, message: String) -> ShaderError {
+    ShaderError::Preprocess { path: path.to_string(), line, message }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    /// Creates a fresh directory with the given files for one test
+    fn files(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = env::temp_dir().join(format!("learn-opengl-rs-preprocess-{}-{}", process::id(), test));
+        let _ = fs::remove_dir_all(&dir);
+        for &(name, code) in files {
+            let path = dir.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, code).unwrap();
+        }
+        dir
+    }
+
+    fn run(dir: &Path, main: &str, defines: &[(&str, &str)], includeDirs: &[PathBuf]) -> Result<Source, ShaderError> {
+        let defines: Vec<(String, String)> = defines.iter().map(|&(n, v)| (n.to_string(), v.to_string())).collect();
+        preprocess(dir.join(main).to_str().unwrap(), &defines, includeDirs)
+    }
+
+    fn error_location(result: Result<Source, ShaderError>) -> (String, usize, String) {
+        match result {
+            Err(ShaderError::Preprocess { path, line, message }) => (path, line, message),
+            Err(error) => panic!("unexpected error {}", error),
+            Ok(_) => panic!("expected an error"),
+        }
+    }
+
+    #[test]
+    fn includes_are_inlined_with_line_directives() {
+        let dir = files("inline", &[
+            ("main.fs", "#version 330 core\n#include \"light.glsl\"\nvoid main() {}\n"),
+            ("light.glsl", "vec3 light() { return vec3(1.0); }\n"),
+        ]);
+        let source = run(&dir, "main.fs", &[], &[]).unwrap();
+        assert_eq!(source.code, "#version 330 core\n#line 2 0\n\
+                                 #line 1 1\nvec3 light() { return vec3(1.0); }\n\
+                                 #line 3 0\nvoid main() {}\n");
+        assert_eq!(source.files.len(), 2);
+        assert!(source.files[1].ends_with("light.glsl"));
+    }
+
+    #[test]
+    fn includes_fall_back_to_include_dirs() {
+        let dir = files("dirs", &[
+            ("shaders/main.fs", "#version 330 core\n#include <common/depth.glsl>\n"),
+            ("include/common/depth.glsl", "float depth;\n"),
+            ("shaders/common/other.glsl", "float other;\n"),
+        ]);
+        assert!(run(&dir, "shaders/main.fs", &[], &[]).is_err());
+        let source = run(&dir, "shaders/main.fs", &[], &[dir.join("include")]).unwrap();
+        assert!(source.code.contains("float depth;\n"));
+    }
+
+    #[test]
+    fn nested_includes_resolve_relative_to_the_including_file() {
+        let dir = files("nested", &[
+            ("main.fs", "#version 330 core\n#include \"lib/a.glsl\"\n"),
+            ("lib/a.glsl", "#include \"b.glsl\"\nfloat a;\n"),
+            ("lib/b.glsl", "float b;\n"),
+        ]);
+        let source = run(&dir, "main.fs", &[], &[]).unwrap();
+        assert_eq!(source.files.len(), 3);
+        assert!(source.code.contains("#line 1 2\nfloat b;\n#line 2 1\nfloat a;\n#line 3 0\n"));
+    }
+
+    #[test]
+    fn defines_follow_the_version() {
+        let dir = files("defines", &[("main.fs", "// comment\n#version 330 core\nvoid main() {}\n")]);
+        let source = run(&dir, "main.fs", &[("NUM_LIGHTS", "4"), ("SHADOWS", "")], &[]).unwrap();
+        assert_eq!(source.code, "// comment\n#version 330 core\n#define NUM_LIGHTS 4\n#define SHADOWS\n\
+                                 #line 3 0\nvoid main() {}\n");
+    }
+
+    #[test]
+    fn defines_without_version_come_first() {
+        let dir = files("defines-no-version", &[("main.glsl", "float x;\n")]);
+        let source = run(&dir, "main.glsl", &[("A", "1")], &[]).unwrap();
+        assert_eq!(source.code, "#define A 1\n#line 1 0\nfloat x;\n");
+    }
+
+    #[test]
+    fn include_cycles_are_reported() {
+        let dir = files("cycle", &[
+            ("main.fs", "#version 330 core\n#include \"a.glsl\"\n"),
+            ("a.glsl", "float a;\n#include \"b.glsl\"\n"),
+            ("b.glsl", "#include \"a.glsl\"\n"),
+        ]);
+        let (path, line, message) = error_location(run(&dir, "main.fs", &[], &[]));
+        assert!(path.ends_with("b.glsl"));
+        assert_eq!(line, 1);
+        assert!(message.starts_with("include cycle: "), "{}", message);
+        assert!(message.ends_with("a.glsl"), "{}", message);
+    }
+
+    #[test]
+    fn missing_includes_point_to_the_directive() {
+        let dir = files("missing", &[("main.fs", "#version 330 core\n\n#include \"nothing.glsl\"\n")]);
+        let (path, line, message) = error_location(run(&dir, "main.fs", &[], &[]));
+        assert!(path.ends_with("main.fs"));
+        assert_eq!(line, 3);
+        assert_eq!(message, "include nothing.glsl not found");
+    }
+
+    #[test]
+    fn version_in_included_file_is_an_error() {
+        let dir = files("version", &[
+            ("main.fs", "#version 330 core\n#include \"a.glsl\"\n"),
+            ("a.glsl", "\n#version 330 core\n"),
+        ]);
+        let (path, line, _) = error_location(run(&dir, "main.fs", &[], &[]));
+        assert!(path.ends_with("a.glsl"));
+        assert_eq!(line, 2);
+    }
+
+    #[test]
+    fn parse_include_accepts_quotes_and_brackets() {
+        assert_eq!(parse_include("#include \"a.glsl\""), Some("a.glsl"));
+        assert_eq!(parse_include("#include <common/pbr.glsl> // BRDF"), Some("common/pbr.glsl"));
+        assert_eq!(parse_include("#include a.glsl"), None);
+        assert_eq!(parse_include("#include \"a.glsl"), None);
+    }
+}
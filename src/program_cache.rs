@@ -0,0 +1,113 @@
+#![allow(non_snake_case)]
+
+/// On-disk cache of linked programs, enabled with `--shader-cache <dir>`. After a program was
+/// linked, `glGetProgramBinary` is stored in `<dir>/<key>.bin`, where the key is a hash of the
+/// preprocessed sources (so also of the `#define`s and `#include`d files) and the driver's
+/// vendor/renderer/version strings. `ShaderBuilder::build` loads it again with `glProgramBinary`
+/// and falls back to compiling when there's no entry or the driver rejects the binary.
+
+use std::ffi::CStr;
+use std::fs;
+use std::os::raw::{c_char, c_void};
+use std::path::{Path, PathBuf};
+
+use gl;
+use gl::types::*;
+
+use utils;
+
+/// Whether program binaries can be retrieved at all (some drivers support zero binary formats)
+pub fn is_supported() -> bool {
+    let (major, minor) = utils::gl_version();
+    if (major, minor) < (4, 1) && !utils::has_extension("GL_ARB_get_program_binary") {
+        return false;
+    }
+    let mut formats = 0;
+    unsafe { gl::GetIntegerv(gl::NUM_PROGRAM_BINARY_FORMATS, &mut formats) };
+    formats > 0
+}
+
+/// Cache key for a program built from the given (preprocessed) stages with the current driver
+pub fn key(stages: &[(GLenum, &str)]) -> u64 {
+    let mut hash = Fnv1a::new();
+    unsafe {
+        for &name in &[gl::VENDOR, gl::RENDERER, gl::VERSION] {
+            let string = gl::GetString(name);
+            if !string.is_null() {
+                hash.write(CStr::from_ptr(string as *const c_char).to_bytes());
+            }
+            hash.write(&[0]);
+        }
+    }
+    for &(type_, code) in stages {
+        hash.write(&type_.to_le_bytes());
+        hash.write(code.as_bytes());
+        hash.write(&[0]);
+    }
+    hash.0
+}
+
+/// Creates a program from the cached binary; None if there's no entry or the driver rejects it
+/// (e.g. after a driver update that didn't change the version string)
+pub unsafe fn load(dir: &Path, key: u64) -> Option<GLuint> {
+    let data = fs::read(path(dir, key)).ok()?;
+    if data.len() < 4 {
+        return None;
+    }
+    let format = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+    let binary = &data[4..];
+
+    let program = gl::CreateProgram();
+    gl::ProgramBinary(program, format, binary.as_ptr() as *const c_void, binary.len() as GLsizei);
+    let mut success = gl::FALSE as GLint;
+    gl::GetProgramiv(program, gl::LINK_STATUS, &mut success);
+    if success != gl::TRUE as GLint {
+        gl::DeleteProgram(program);
+        return None;
+    }
+    Some(program)
+}
+
+/// Must be called before linking so that the driver keeps the binary around for `store`
+pub unsafe fn prepare(program: GLuint) {
+    gl::ProgramParameteri(program, gl::PROGRAM_BINARY_RETRIEVABLE_HINT, gl::TRUE as GLint);
+}
+
+/// Writes the binary of a linked program into the cache
+pub unsafe fn store(dir: &Path, key: u64, program: GLuint) -> Result<(), String> {
+    let mut length = 0;
+    gl::GetProgramiv(program, gl::PROGRAM_BINARY_LENGTH, &mut length);
+    if length <= 0 {
+        return Err("the driver didn't provide a program binary".into());
+    }
+    let mut binary = vec![0u8; length as usize];
+    let (mut written, mut format) = (0, 0);
+    gl::GetProgramBinary(program, length, &mut written, &mut format, binary.as_mut_ptr() as *mut c_void);
+    binary.truncate(written as usize);
+
+    let mut data = format.to_le_bytes().to_vec();
+    data.extend_from_slice(&binary);
+    let path = path(dir, key);
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    fs::write(&path, data).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn path(dir: &Path, key: u64) -> PathBuf {
+    dir.join(format!("{:016x}.bin", key))
+}
+
+/// 64 bit FNV-1a, stable across runs and Rust versions (unlike `DefaultHasher`)
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Fnv1a {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
@@ -0,0 +1,274 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+
+/// Introspection of linked programs: active attributes, uniforms, uniform blocks (with the std140
+/// or driver-chosen member offsets) and the texture units the samplers are set to. `Display`
+/// prints a readable summary, e.g. `println!("{}", shader.reflect())`.
+
+use std::ffi::CString;
+use std::fmt;
+
+use gl;
+use gl::types::*;
+
+use mesh;
+use shader::Shader;
+use uniform;
+
+pub struct Reflection {
+    pub attributes: Vec<Attribute>,
+    /// uniforms in the default block
+    pub uniforms: Vec<UniformVariable>,
+    pub blocks: Vec<UniformBlock>,
+    pub samplers: Vec<SamplerBinding>,
+}
+
+pub struct Attribute {
+    pub name: String,
+    pub location: GLint,
+    pub type_: GLenum,
+    pub size: GLint,
+}
+
+pub struct UniformVariable {
+    pub name: String,
+    pub location: GLint,
+    pub type_: GLenum,
+    pub size: GLint,
+}
+
+pub struct UniformBlock {
+    pub name: String,
+    pub index: GLuint,
+    pub binding: GLuint,
+    /// minimum buffer size in bytes
+    pub dataSize: GLint,
+    pub members: Vec<BlockMember>,
+}
+
+pub struct BlockMember {
+    pub name: String,
+    pub type_: GLenum,
+    pub size: GLint,
+    /// byte offset from the start of the block
+    pub offset: GLint,
+    /// bytes between array elements (0 if not an array)
+    pub arrayStride: GLint,
+    /// bytes between matrix columns (or rows, if row major; 0 if not a matrix)
+    pub matrixStride: GLint,
+    pub rowMajor: bool,
+}
+
+pub struct SamplerBinding {
+    pub name: String,
+    pub type_: GLenum,
+    /// texture unit the sampler is currently set to
+    pub unit: GLint,
+}
+
+impl Shader {
+    /// Queries the interface of the linked program
+    pub fn reflect(&self) -> Reflection {
+        unsafe {
+            let program = self.ID;
+            let attributes = activeAttributes(program);
+            let mut uniforms = Vec::new();
+            let mut members: Vec<(GLint, BlockMember)> = Vec::new();
+            let mut samplers = Vec::new();
+
+            let mut count = 0;
+            gl::GetProgramiv(program, gl::ACTIVE_UNIFORMS, &mut count);
+            for index in 0..count as GLuint {
+                let (name, type_, size) = activeUniform(program, index);
+                let blockIndex = uniformParameter(program, index, gl::UNIFORM_BLOCK_INDEX);
+                if blockIndex != -1 {
+                    members.push((blockIndex, BlockMember {
+                        name,
+                        type_,
+                        size,
+                        offset: uniformParameter(program, index, gl::UNIFORM_OFFSET),
+                        arrayStride: uniformParameter(program, index, gl::UNIFORM_ARRAY_STRIDE),
+                        matrixStride: uniformParameter(program, index, gl::UNIFORM_MATRIX_STRIDE),
+                        rowMajor: uniformParameter(program, index, gl::UNIFORM_IS_ROW_MAJOR) != 0,
+                    }));
+                    continue;
+                }
+
+                let location = gl::GetUniformLocation(program, CString::new(name.as_str()).unwrap().as_ptr());
+                if is_sampler(type_) {
+                    // report each element of sampler arrays separately
+                    let base = name.trim_end_matches("[0]").to_string();
+                    for i in 0..size {
+                        let element = if size > 1 { format!("{}[{}]", base, i) } else { name.clone() };
+                        let location = gl::GetUniformLocation(program, CString::new(element.as_str()).unwrap().as_ptr());
+                        let mut unit = 0;
+                        gl::GetUniformiv(program, location, &mut unit);
+                        samplers.push(SamplerBinding { name: element, type_, unit });
+                    }
+                }
+                uniforms.push(UniformVariable { name, location, type_, size });
+            }
+
+            let mut blockCount = 0;
+            gl::GetProgramiv(program, gl::ACTIVE_UNIFORM_BLOCKS, &mut blockCount);
+            let mut blocks: Vec<_> = (0..blockCount as GLuint).map(|index| {
+                let mut name = vec![0u8; 256];
+                let mut length = 0;
+                gl::GetActiveUniformBlockName(program, index, name.len() as GLsizei, &mut length,
+                                              name.as_mut_ptr() as *mut GLchar);
+                name.truncate(length as usize);
+                let (mut binding, mut dataSize) = (0, 0);
+                gl::GetActiveUniformBlockiv(program, index, gl::UNIFORM_BLOCK_BINDING, &mut binding);
+                gl::GetActiveUniformBlockiv(program, index, gl::UNIFORM_BLOCK_DATA_SIZE, &mut dataSize);
+                UniformBlock {
+                    name: String::from_utf8_lossy(&name).into_owned(),
+                    index,
+                    binding: binding as GLuint,
+                    dataSize,
+                    members: Vec::new(),
+                }
+            }).collect();
+            for (blockIndex, member) in members {
+                blocks[blockIndex as usize].members.push(member);
+            }
+            for block in &mut blocks {
+                block.members.sort_by_key(|member| member.offset);
+            }
+
+            Reflection { attributes, uniforms, blocks, samplers }
+        }
+    }
+}
+
+impl Reflection {
+    pub fn attribute(&self, name: &str) -> Option<&Attribute> {
+        self.attributes.iter().find(|attribute| attribute.name == name)
+    }
+
+    pub fn uniform(&self, name: &str) -> Option<&UniformVariable> {
+        self.uniforms.iter().find(|uniform| uniform.name == name)
+    }
+
+    pub fn block(&self, name: &str) -> Option<&UniformBlock> {
+        self.blocks.iter().find(|block| block.name == name)
+    }
+
+    /// Checks that every active attribute is at a location that `Mesh` provides with a matching
+    /// type (see `mesh::VERTEX_ATTRIBUTES`). Returns a description of each mismatch.
+    pub fn check_mesh_attributes(&self) -> Vec<String> {
+        self.attributes.iter().filter_map(|attribute| {
+            match mesh::VERTEX_ATTRIBUTES.iter().find(|&&(location, _, _)| location as GLint == attribute.location) {
+                None => Some(format!("attribute `{}` at location {} isn't provided by Mesh",
+                                     attribute.name, attribute.location)),
+                Some(&(_, type_, field)) if type_ != attribute.type_ =>
+                    Some(format!("attribute `{}` at location {} is a {}, but Mesh provides Vertex::{} as {}",
+                                 attribute.name, attribute.location, glsl_type(attribute.type_), field, glsl_type(type_))),
+                _ => None,
+            }
+        }).collect()
+    }
+}
+
+impl fmt::Display for Reflection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "attributes:")?;
+        for a in &self.attributes {
+            writeln!(f, "  layout (location = {}) in {} {}{}", a.location, glsl_type(a.type_), a.name, array(a.size))?;
+        }
+        writeln!(f, "uniforms:")?;
+        for u in &self.uniforms {
+            writeln!(f, "  {} {}{} (location {})", glsl_type(u.type_), u.name, array(u.size), u.location)?;
+        }
+        for s in &self.samplers {
+            writeln!(f, "  {} -> texture unit {}", s.name, s.unit)?;
+        }
+        for b in &self.blocks {
+            writeln!(f, "uniform block {} (binding {}, {} bytes):", b.name, b.binding, b.dataSize)?;
+            for m in &b.members {
+                write!(f, "  {:4}: {} {}{}", m.offset, glsl_type(m.type_), m.name, array(m.size))?;
+                if m.arrayStride > 0 {
+                    write!(f, ", array stride {}", m.arrayStride)?;
+                }
+                if m.matrixStride > 0 {
+                    write!(f, ", matrix stride {}{}", m.matrixStride, if m.rowMajor { " (row major)" } else { "" })?;
+                }
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn array(size: GLint) -> String {
+    if size > 1 { format!("[{}]", size) } else { String::new() }
+}
+
+unsafe fn activeAttributes(program: GLuint) -> Vec<Attribute> {
+    let mut count = 0;
+    gl::GetProgramiv(program, gl::ACTIVE_ATTRIBUTES, &mut count);
+    let mut attributes: Vec<_> = (0..count as GLuint).map(|index| {
+        let mut name = vec![0u8; 256];
+        let (mut length, mut size, mut type_) = (0, 0, 0);
+        gl::GetActiveAttrib(program, index, name.len() as GLsizei, &mut length, &mut size, &mut type_,
+                            name.as_mut_ptr() as *mut GLchar);
+        name.truncate(length as usize);
+        let name = String::from_utf8_lossy(&name).into_owned();
+        let location = gl::GetAttribLocation(program, CString::new(name.as_str()).unwrap().as_ptr());
+        Attribute { name, location, type_, size }
+    }).collect();
+    // built-ins like gl_VertexID are reported with location -1
+    attributes.retain(|attribute| attribute.location >= 0);
+    attributes.sort_by_key(|attribute| attribute.location);
+    attributes
+}
+
+unsafe fn activeUniform(program: GLuint, index: GLuint) -> (String, GLenum, GLint) {
+    let mut name = vec![0u8; 256];
+    let (mut length, mut size, mut type_) = (0, 0, 0);
+    gl::GetActiveUniform(program, index, name.len() as GLsizei, &mut length, &mut size, &mut type_,
+                         name.as_mut_ptr() as *mut GLchar);
+    name.truncate(length as usize);
+    (String::from_utf8_lossy(&name).into_owned(), type_, size)
+}
+
+unsafe fn uniformParameter(program: GLuint, index: GLuint, pname: GLenum) -> GLint {
+    let mut value = 0;
+    gl::GetActiveUniformsiv(program, 1, &index, pname, &mut value);
+    value
+}
+
+/// GLSL name of a uniform/attribute type
+pub fn glsl_type(type_: GLenum) -> String {
+    uniform::type_name(type_)
+        .or_else(|| sampler_name(type_))
+        .map_or_else(|| format!("<type 0x{:x}>", type_), |name| name.to_string())
+}
+
+pub fn is_sampler(type_: GLenum) -> bool {
+    sampler_name(type_).is_some()
+}
+
+fn sampler_name(type_: GLenum) -> Option<&'static str> {
+    Some(match type_ {
+        gl::SAMPLER_1D => "sampler1D",
+        gl::SAMPLER_2D => "sampler2D",
+        gl::SAMPLER_3D => "sampler3D",
+        gl::SAMPLER_CUBE => "samplerCube",
+        gl::SAMPLER_1D_SHADOW => "sampler1DShadow",
+        gl::SAMPLER_2D_SHADOW => "sampler2DShadow",
+        gl::SAMPLER_1D_ARRAY => "sampler1DArray",
+        gl::SAMPLER_2D_ARRAY => "sampler2DArray",
+        gl::SAMPLER_2D_ARRAY_SHADOW => "sampler2DArrayShadow",
+        gl::SAMPLER_CUBE_SHADOW => "samplerCubeShadow",
+        gl::SAMPLER_2D_MULTISAMPLE => "sampler2DMS",
+        gl::SAMPLER_2D_MULTISAMPLE_ARRAY => "sampler2DMSArray",
+        gl::SAMPLER_2D_RECT => "sampler2DRect",
+        gl::SAMPLER_BUFFER => "samplerBuffer",
+        gl::SAMPLER_CUBE_MAP_ARRAY => "samplerCubeArray",
+        gl::INT_SAMPLER_2D => "isampler2D",
+        gl::INT_SAMPLER_3D => "isampler3D",
+        gl::UNSIGNED_INT_SAMPLER_2D => "usampler2D",
+        gl::UNSIGNED_INT_SAMPLER_3D => "usampler3D",
+        _ => return None,
+    })
+}
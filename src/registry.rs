@@ -0,0 +1,362 @@
+//! Registry of all tutorials compiled into the binary (see the `chapter-N` feature flags).
+//! Replaces the hand-written `match` that used to live in `main()` and lets other code
+//! enumerate tutorials, look them up by id and suggest close matches for typos.
+
+#[cfg(feature = "chapter-1")]
+use _1_getting_started::*;
+#[cfg(feature = "chapter-2")]
+use _2_lighting::*;
+#[cfg(feature = "chapter-3")]
+use _3_model_loading::*;
+#[cfg(feature = "chapter-4")]
+use _4_advanced_opengl::*;
+#[cfg(feature = "chapter-5")]
+use _5_advanced_lighting::*;
+#[cfg(feature = "chapter-6")]
+use _6_pbr::*;
+#[cfg(feature = "chapter-7")]
+use _7_in_practice::*;
+#[cfg(feature = "chapter-8")]
+use _8_guest_articles::*;
+
+pub struct Chapter {
+    pub number: u32,
+    pub title: &'static str,
+    /// source directory, relative to the repository root
+    pub dir: &'static str,
+    pub tutorials: &'static [Tutorial],
+}
+
+pub struct Tutorial {
+    /// id used on the command line, e.g. `1_3_2`
+    pub id: &'static str,
+    pub title: &'static str,
+    /// source file name, relative to the chapter directory
+    pub file: &'static str,
+    /// entry function, e.g. `main_1_3_2`
+    pub run: fn(),
+    /// one sentence about what the tutorial shows, printed by `describe`
+    pub description: &'static str,
+}
+
+/// All chapters whose feature is enabled, in order
+pub static CHAPTERS: &[Chapter] = &[
+    #[cfg(feature = "chapter-1")]
+    Chapter {
+        number: 1,
+        title: "Getting started",
+        dir: "src/_1_getting_started",
+        tutorials: &[
+            Tutorial { id: "1_1_1", title: "Hello Window", file: "_1_1_hello_window.rs", run: main_1_1_1,
+                       description: "Opens a window and processes input until Escape is pressed" },
+            Tutorial { id: "1_1_2", title: "Hello Window Clear", file: "_1_2_hello_window_clear.rs", run: main_1_1_2,
+                       description: "Clears the window to a solid color every frame" },
+            Tutorial { id: "1_2_1", title: "Hello Triangle", file: "_2_1_hello_triangle.rs", run: main_1_2_1,
+                       description: "Draws an orange triangle from a vertex buffer" },
+            Tutorial { id: "1_2_2", title: "Hello Triangle Indexed", file: "_2_2_hello_triangle_indexed.rs", run: main_1_2_2,
+                       description: "Draws a rectangle from two triangles with an element buffer" },
+            Tutorial { id: "1_2_3", title: "Hello Triangle Exercise 1", file: "_2_3_hello_triangle_exercise1.rs", run: main_1_2_3,
+                       description: "Draws two triangles next to each other from one vertex buffer" },
+            Tutorial { id: "1_2_4", title: "Hello Triangle Exercise 2", file: "_2_4_hello_triangle_exercise2.rs", run: main_1_2_4,
+                       description: "Draws the two triangles from separate VAOs and VBOs" },
+            Tutorial { id: "1_2_5", title: "Hello Triangle Exercise 3", file: "_2_5_hello_triangle_exercise3.rs", run: main_1_2_5,
+                       description: "Draws the two triangles with different fragment shaders" },
+            Tutorial { id: "1_3_1", title: "Shaders Uniform", file: "_3_1_shaders_uniform.rs", run: main_1_3_1,
+                       description: "Changes the color of a triangle over time with a uniform" },
+            Tutorial { id: "1_3_2", title: "Shaders Interpolation", file: "_3_2_shaders_interpolation.rs", run: main_1_3_2,
+                       description: "Interpolates per-vertex colors across a triangle" },
+            Tutorial { id: "1_3_3", title: "Shaders Class", file: "_3_3_shaders_class.rs", run: main_1_3_3,
+                       description: "Loads the shaders through the Shader struct" },
+            Tutorial { id: "1_4_1", title: "Textures", file: "_4_1_textures.rs", run: main_1_4_1,
+                       description: "Textures a rectangle with a wooden container image" },
+            Tutorial { id: "1_4_2", title: "Textures Combined", file: "_4_2_textures_combined.rs", run: main_1_4_2,
+                       description: "Mixes two textures on a rectangle" },
+            Tutorial { id: "1_4_3", title: "Textures Exercise 2", file: "_4_3_textures_exercise2.rs", run: main_1_4_3,
+                       description: "Repeats the face texture with GL_REPEAT wrapping" },
+            Tutorial { id: "1_4_4", title: "Textures Exercise 3", file: "_4_4_textures_exercise3.rs", run: main_1_4_4,
+                       description: "Shows a magnified part of the textures with nearest filtering" },
+            Tutorial { id: "1_4_5", title: "Textures Exercise 4", file: "_4_5_textures_exercise4.rs", run: main_1_4_5,
+                       description: "Changes the mix of the two textures with the up and down keys" },
+            Tutorial { id: "1_5_1", title: "Transformations", file: "_5_1_transformations.rs", run: main_1_5_1,
+                       description: "Rotates and translates the textured rectangle with a transform matrix" },
+            Tutorial { id: "1_5_2", title: "Transformations Exercise 2", file: "_5_2_transformations_exercise2.rs", run: main_1_5_2,
+                       description: "Draws a second rectangle that scales over time" },
+            Tutorial { id: "1_6_1", title: "Coordinate Systems", file: "_6_1_coordinate_systems.rs", run: main_1_6_1,
+                       description: "Projects a plane with model, view and projection matrices" },
+            Tutorial { id: "1_6_2", title: "Coordinate Systems Depth", file: "_6_2_coordinate_systems_depth.rs", run: main_1_6_2,
+                       description: "Draws a rotating cube with depth testing" },
+            Tutorial { id: "1_6_3", title: "Coordinate Systems Multiple", file: "_6_3_coordinate_systems_multiple.rs", run: main_1_6_3,
+                       description: "Draws ten rotated cubes at different positions" },
+            Tutorial { id: "1_7_1", title: "Camera Circle", file: "_7_1_camera_circle.rs", run: main_1_7_1,
+                       description: "Circles the camera around the cubes" },
+            Tutorial { id: "1_7_2", title: "Camera Keyboard Delta Time", file: "_7_2_camera_keyboard_dt.rs", run: main_1_7_2,
+                       description: "Moves the camera with WASD, scaled by the frame time" },
+            Tutorial { id: "1_7_3", title: "Camera Mouse Zoom", file: "_7_3_camera_mouse_zoom.rs", run: main_1_7_3,
+                       description: "Looks around with the mouse and zooms with the scroll wheel" },
+            Tutorial { id: "1_7_4", title: "Camera Class", file: "_7_4_camera_class.rs", run: main_1_7_4,
+                       description: "Flies through the cubes with the Camera struct" },
+        ],
+    },
+    #[cfg(feature = "chapter-2")]
+    Chapter {
+        number: 2,
+        title: "Lighting",
+        dir: "src/_2_lighting",
+        tutorials: &[
+            Tutorial { id: "2_1", title: "Colors", file: "_1_colors.rs", run: main_2_1,
+                       description: "Lights a coral cube with a white lamp cube" },
+            Tutorial { id: "2_2_1", title: "Basic Lighting Diffuse", file: "_2_1_basic_lighting_diffuse.rs", run: main_2_2_1,
+                       description: "Adds ambient and diffuse lighting" },
+            Tutorial { id: "2_2_2", title: "Basic Lighting Specular", file: "_2_2_basic_lighting_specular.rs", run: main_2_2_2,
+                       description: "Adds specular highlights (Phong)" },
+            Tutorial { id: "2_3_1", title: "Materials", file: "_3_1_materials.rs", run: main_2_3_1,
+                       description: "Uses a material struct and a light with changing colors" },
+            Tutorial { id: "2_3_2", title: "Materials Exercise 1", file: "_3_2_materials_exercise1.rs", run: main_2_3_2,
+                       description: "Uses the cyan plastic material with a white light" },
+            Tutorial { id: "2_4_1", title: "Lighting Maps Diffuse Map", file: "_4_1_lighting_maps_diffuse_map.rs", run: main_2_4_1,
+                       description: "Takes the diffuse color from the container texture" },
+            Tutorial { id: "2_4_2", title: "Lighting Maps Specular Map", file: "_4_2_lighting_maps_specular_map.rs", run: main_2_4_2,
+                       description: "Adds a specular map for the steel border" },
+            Tutorial { id: "2_4_3", title: "Lighting Maps Exercise 4", file: "_4_3_lighting_maps_exercise4.rs", run: main_2_4_3,
+                       description: "Adds an emission map to the container" },
+            Tutorial { id: "2_5_1", title: "Light Casters Directional", file: "_5_1_light_casters_directional.rs", run: main_2_5_1,
+                       description: "Lights the cubes with a directional light" },
+            Tutorial { id: "2_5_2", title: "Light Casters Point", file: "_5_2_light_casters_point.rs", run: main_2_5_2,
+                       description: "Lights the cubes with an attenuated point light" },
+            Tutorial { id: "2_5_3", title: "Light Casters Spot", file: "_5_3_light_casters_spot.rs", run: main_2_5_3,
+                       description: "Lights the cubes with a flashlight (spotlight)" },
+            Tutorial { id: "2_5_4", title: "Light Casters Spot Soft", file: "_5_4_light_casters_spot_soft.rs", run: main_2_5_4,
+                       description: "Gives the spotlight smooth edges" },
+            Tutorial { id: "2_6", title: "Multiple Lights", file: "_6_multiple_lights.rs", run: main_2_6,
+                       description: "Combines a directional light, four point lights and a spotlight" },
+        ],
+    },
+    #[cfg(feature = "chapter-3")]
+    Chapter {
+        number: 3,
+        title: "Model loading",
+        dir: "src/_3_model_loading",
+        tutorials: &[
+            Tutorial { id: "3_1", title: "Model Loading", file: "_1_model_loading.rs", run: main_3_1,
+                       description: "Loads the nanosuit model from an OBJ file" },
+        ],
+    },
+    #[cfg(feature = "chapter-4")]
+    Chapter {
+        number: 4,
+        title: "Advanced OpenGL",
+        dir: "src/_4_advanced_opengl",
+        tutorials: &[
+            Tutorial { id: "4_1_1", title: "Depth Testing", file: "_1_1_depth_testing.rs", run: main_4_1_1,
+                       description: "Draws cubes on a floor with depth testing" },
+            Tutorial { id: "4_1_2", title: "Depth Testing View", file: "_1_2_depth_testing_view.rs", run: main_4_1_2,
+                       description: "Visualizes the linearized depth buffer" },
+            Tutorial { id: "4_2", title: "Stencil Testing", file: "_2_stencil_testing.rs", run: main_4_2,
+                       description: "Outlines the cubes using the stencil buffer" },
+            Tutorial { id: "4_3_1", title: "Blending Discard", file: "_3_1_blending_discard.rs", run: main_4_3_1,
+                       description: "Discards transparent fragments of grass textures" },
+            Tutorial { id: "4_3_2", title: "Blending Sorted", file: "_3_2_blending_sorted.rs", run: main_4_3_2,
+                       description: "Blends semi-transparent windows sorted back to front" },
+            Tutorial { id: "4_5_1", title: "Framebuffers", file: "_5_1_framebuffers.rs", run: main_4_5_1,
+                       description: "Renders the scene into a framebuffer and draws it on a screen quad" },
+            Tutorial { id: "4_5_2", title: "Framebuffers Exercise 1", file: "_5_2_framebuffers_exercise1.rs", run: main_4_5_2,
+                       description: "Shows a rear-view mirror rendered into a framebuffer" },
+            Tutorial { id: "4_6_1", title: "Cubemaps Skybox", file: "_6_1_cubemaps_skybox.rs", run: main_4_6_1,
+                       description: "Draws a skybox from a cubemap" },
+            Tutorial { id: "4_6_2", title: "Cubemaps Environment Mapping", file: "_6_2_cubemaps_environment_mapping.rs", run: main_4_6_2,
+                       description: "Reflects the skybox on a cube" },
+            Tutorial { id: "4_8", title: "Advanced GLSL UBO", file: "_8_advanced_glsl_ubo.rs", run: main_4_8,
+                       description: "Shares the projection and view matrices between shaders with a uniform buffer" },
+            Tutorial { id: "4_9_1", title: "Geometry Shader Houses", file: "_9_1_geometry_shader_houses.rs", run: main_4_9_1,
+                       description: "Builds houses from points in a geometry shader" },
+            Tutorial { id: "4_9_2", title: "Geometry Shader Exploding", file: "_9_2_geometry_shader_exploding.rs", run: main_4_9_2,
+                       description: "Explodes the nanosuit's triangles along their normals in a geometry shader" },
+            Tutorial { id: "4_9_3", title: "Normal Visualization", file: "_9_3_normal_visualization.rs", run: main_4_9_3,
+                       description: "Draws the normals of the nanosuit with a geometry shader" },
+            Tutorial { id: "4_10_1", title: "Instancing Quads", file: "_10_1_instancing_quads.rs", run: main_4_10_1,
+                       description: "Draws 100 quads with instanced arrays" },
+            Tutorial { id: "4_10_2", title: "Asteroids", file: "_10_2_asteroids.rs", run: main_4_10_2,
+                       description: "Draws a planet with an asteroid belt, one draw call per rock" },
+            Tutorial { id: "4_10_3", title: "Asteroids Instanced", file: "_10_3_asteroids_instanced.rs", run: main_4_10_3,
+                       description: "Draws the asteroid belt with instancing" },
+            Tutorial { id: "4_11", title: "Anti Aliasing Offscreen", file: "_11_anti_aliasing_offscreen.rs", run: main_4_11,
+                       description: "Resolves a multisampled framebuffer and post-processes it" },
+        ],
+    },
+    #[cfg(feature = "chapter-5")]
+    Chapter {
+        number: 5,
+        title: "Advanced Lighting",
+        dir: "src/_5_advanced_lighting",
+        tutorials: &[
+            Tutorial { id: "5_1", title: "Advanced Lighting", file: "_1_advanced_lighting.rs", run: main_5_1,
+                       description: "Compares Blinn-Phong and Phong specular on a wooden floor" },
+            Tutorial { id: "5_2", title: "Gamma Correction", file: "_2_gamma_correction.rs", run: main_5_2,
+                       description: "Compares gamma-corrected and linear lighting" },
+            Tutorial { id: "5_3_1_1", title: "Shadow Mapping Depth", file: "_3_1_1_shadow_mapping_depth.rs", run: main_5_3_1_1,
+                       description: "Renders the depth map of a directional light" },
+            Tutorial { id: "5_3_1_2", title: "Shadow Mapping Base", file: "_3_1_2_shadow_mapping_base.rs", run: main_5_3_1_2,
+                       description: "Shadows the scene with the depth map" },
+            Tutorial { id: "5_3_1_3", title: "Shadow Mapping", file: "_3_1_3_shadow_mapping.rs", run: main_5_3_1_3,
+                       description: "Improves the shadows with bias, PCF and over-sampling fixes" },
+            Tutorial { id: "5_3_2_1", title: "Point Shadows", file: "_3_2_1_point_shadows.rs", run: main_5_3_2_1,
+                       description: "Shadows a point light with a depth cubemap" },
+            Tutorial { id: "5_3_2_2", title: "Point Shadows Soft", file: "_3_2_2_point_shadows_soft.rs", run: main_5_3_2_2,
+                       description: "Softens the point light shadows with PCF" },
+            Tutorial { id: "5_3_3", title: "Cascaded Shadow Maps", file: "_3_3_csm.rs", run: main_5_3_3,
+                       description: "Shadows a large scene with cascaded shadow maps" },
+            Tutorial { id: "5_4", title: "Normal Mapping", file: "_4_normal_mapping.rs", run: main_5_4,
+                       description: "Adds detail to a brick wall with a normal map" },
+            Tutorial { id: "5_4_2", title: "Normal Mapping Model", file: "_4_2_normal_mapping_model.rs", run: main_5_4_2,
+                       description: "Normal maps the cyborg model with generated tangents" },
+            Tutorial { id: "5_5_1", title: "Parallax Mapping", file: "_5_1_parallax_mapping.rs", run: main_5_5_1,
+                       description: "Offsets texture coordinates with a height map (parallax mapping)" },
+            Tutorial { id: "5_5_2", title: "Steep Parallax Mapping", file: "_5_2_steep_parallax_mapping.rs", run: main_5_5_2,
+                       description: "Improves the parallax effect with steep parallax mapping" },
+            Tutorial { id: "5_5_3", title: "Parallax Occlusion Mapping", file: "_5_3_parallax_occlusion_mapping.rs", run: main_5_5_3,
+                       description: "Refines steep parallax mapping with parallax occlusion mapping" },
+            Tutorial { id: "5_6", title: "HDR", file: "_6_hdr.rs", run: main_5_6,
+                       description: "Renders a bright tunnel into a floating point framebuffer with tone mapping" },
+            Tutorial { id: "5_7", title: "Bloom", file: "_7_bloom.rs", run: main_5_7,
+                       description: "Blurs the bright parts of the scene for a bloom effect" },
+        ],
+    },
+    #[cfg(feature = "chapter-6")]
+    Chapter {
+        number: 6,
+        title: "PBR",
+        dir: "src/_6_pbr",
+        tutorials: &[
+            Tutorial { id: "6_1_1", title: "Lighting", file: "_1_1_lighting.rs", run: main_6_1_1,
+                       description: "Lights a grid of spheres with varying metallic and roughness values" },
+            Tutorial { id: "6_1_2", title: "Lighting Textured", file: "_1_2_lighting_textured.rs", run: main_6_1_2,
+                       description: "Lights a sphere with PBR textures" },
+        ],
+    },
+    #[cfg(feature = "chapter-7")]
+    Chapter {
+        number: 7,
+        title: "In Practice",
+        dir: "src/_7_in_practice",
+        tutorials: &[
+            Tutorial { id: "7_1", title: "Debugging", file: "_1_debugging.rs", run: main_7_1,
+                       description: "Checks for OpenGL errors and prints debug output messages" },
+        ],
+    },
+    #[cfg(feature = "chapter-8")]
+    Chapter {
+        number: 8,
+        title: "Guest Articles",
+        dir: "src/_8_guest_articles",
+        tutorials: &[
+            Tutorial { id: "8_1", title: "Tessellation Terrain", file: "_1_tessellation_terrain.rs", run: main_8_1,
+                       description: "Tessellates a terrain on the GPU from a height map" },
+            Tutorial { id: "8_2", title: "Skeletal Animation", file: "_2_skeletal_animation.rs", run: main_8_2,
+                       description: "Animates a skinned glTF model with its skeleton" },
+        ],
+    },
+];
+
+/// Iterates over all compiled-in tutorials together with their chapter
+pub fn tutorials() -> impl Iterator<Item = (&'static Chapter, &'static Tutorial)> {
+    CHAPTERS.iter().flat_map(|chapter| chapter.tutorials.iter().map(move |tutorial| (chapter, tutorial)))
+}
+
+pub fn find(id: &str) -> Option<(&'static Chapter, &'static Tutorial)> {
+    tutorials().find(|&(_, tutorial)| tutorial.id == id)
+}
+
+/// Returns tutorials whose id is within a small edit distance of `query` (e.g. `5_3_1_2` for `5_3_2_1`)
+/// or whose title contains it (case-insensitive), best matches first.
+pub fn suggest(query: &str) -> Vec<&'static Tutorial> {
+    let query_lower = query.to_lowercase();
+    let mut matches: Vec<(usize, &'static Tutorial)> = tutorials()
+        .filter_map(|(_, tutorial)| {
+            let distance = edit_distance(query, tutorial.id);
+            if distance <= 2 {
+                Some((distance, tutorial))
+            } else if tutorial.title.to_lowercase().contains(&query_lower) {
+                Some((3, tutorial))
+            } else {
+                None
+            }
+        })
+        .collect();
+    matches.sort_by_key(|&(distance, _)| distance);
+    matches.into_iter().map(|(_, tutorial)| tutorial).collect()
+}
+
+/// Levenshtein distance between two ids
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for j in 0..=b.len() {
+        d[0][j] = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            d[i][j] = (d[i - 1][j] + 1).min(d[i][j - 1] + 1).min(d[i - 1][j - 1] + cost);
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(tutorials: Vec<&Tutorial>) -> Vec<&str> {
+        tutorials.iter().map(|tutorial| tutorial.id).collect()
+    }
+
+    #[test]
+    fn edit_distance_counts_single_edits() {
+        assert_eq!(edit_distance("5_3_1_2", "5_3_1_2"), 0);
+        assert_eq!(edit_distance("5_3_1_2", "5_3_2_1"), 2);
+        assert_eq!(edit_distance("5_312", "5_3_1_2"), 2);
+        assert_eq!(edit_distance("", "4_8"), 3);
+    }
+
+    #[test]
+    fn ids_are_unique() {
+        let mut all: Vec<&str> = tutorials().map(|(_, tutorial)| tutorial.id).collect();
+        let count = all.len();
+        all.sort();
+        all.dedup();
+        assert_eq!(all.len(), count);
+    }
+
+    #[cfg(feature = "chapter-5")]
+    #[test]
+    fn suggest_swapped_digits() {
+        let suggestions = ids(suggest("5_3_2_1"));
+        assert_eq!(suggestions[0], "5_3_2_1");
+        assert!(suggestions.contains(&"5_3_1_2"));
+    }
+
+    #[cfg(feature = "chapter-5")]
+    #[test]
+    fn suggest_closest_ids_first() {
+        // one substitution away, in registry order, before the ids two edits away
+        let suggestions = ids(suggest("5_3_2_3"));
+        assert_eq!(&suggestions[..3], &["5_3_1_3", "5_3_2_1", "5_3_2_2"]);
+        assert!(suggestions.contains(&"5_3_1_1"));
+    }
+
+    #[cfg(feature = "chapter-5")]
+    #[test]
+    fn suggest_by_title() {
+        assert_eq!(ids(suggest("bloom")), vec!["5_7"]);
+        assert!(ids(suggest("Shadow")).contains(&"5_3_3"));
+    }
+
+    #[test]
+    fn suggest_nothing_for_unrelated_query() {
+        assert!(suggest("unrelated query").is_empty());
+    }
+}
@@ -1,189 +1,651 @@
 #![allow(non_snake_case)]
 
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
 use std::ffi::{CString, CStr};
-use std::fs::File;
-use std::io::Read;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::ptr;
-use std::str;
+use std::time::{Duration, Instant, SystemTime};
 
 use gl;
 use gl::types::*;
 
-use cgmath::{Matrix, Matrix4, Vector3};
-use cgmath::prelude::*;
+use cgmath::{Matrix4, Vector3, vec3};
+
+use context;
+use preprocess;
+use program_cache;
+use uniform::{self, RecordedUniform, Uniform};
+use utils;
+
+/// How often the source files of a watched shader are checked for changes
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
 
 pub struct Shader {
     pub ID: u32,
+    /// the active uniforms by name, queried after linking (see `queryUniforms`)
+    uniforms: RefCell<HashMap<String, UniformInfo>>,
+    /// uniform names that were already warned about in `set`
+    warned: RefCell<HashSet<String>>,
+    /// only set with `--watch-shaders`, see `reloadIfChanged`
+    watch: Option<RefCell<Watch>>,
+}
+
+/// An active uniform as reported by `glGetActiveUniform`
+#[derive(Clone, Copy, Debug)]
+pub struct UniformInfo {
+    pub location: GLint,
+    pub type_: GLenum,
+    /// number of array elements (from this element on, for `name[i]` entries)
+    pub size: GLint,
+}
+
+/// State for hot-reloading: the source files with their last modification time, and the
+/// uniform values set so far, which are lost when the program is relinked
+struct Watch {
+    builder: ShaderBuilder,
+    files: Vec<(String, Option<SystemTime>)>,
+    lastCheck: Instant,
+    uniforms: HashMap<String, RecordedUniform>,
+}
+
+/// Errors while building a shader program. Compile logs are rewritten so that lines referring
+/// to a source location start with `path:line:`.
+#[derive(Debug)]
+pub enum ShaderError {
+    Io { path: String, error: io::Error },
+    /// invalid or unresolvable `#include`, see preprocess.rs
+    Preprocess { path: String, line: usize, message: String },
+    Compile { stage: &'static str, log: String },
+    Link { log: String },
+    /// the stage needs a newer OpenGL version than the context provides
+    Unsupported { stage: &'static str, required: (u32, u32), version: (u32, u32) },
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ShaderError::Io { ref path, ref error } => write!(f, "Failed to read {}: {}", path, error),
+            ShaderError::Preprocess { ref path, line, ref message } => write!(f, "{}:{}: {}", path, line, message),
+            ShaderError::Compile { stage, ref log } =>
+                write!(f, "ERROR::SHADER_COMPILATION_ERROR of type: {}\n{}", stage, log),
+            ShaderError::Link { ref log } => write!(f, "ERROR::PROGRAM_LINKING_ERROR\n{}", log),
+            ShaderError::Unsupported { stage, required, version } =>
+                write!(f, "ERROR::SHADER_UNSUPPORTED {} shaders require OpenGL {}.{}, but the context only supports {}.{}",
+                       stage, required.0, required.1, version.0, version.1),
+        }
+    }
+}
+
+impl Error for ShaderError {}
+
+/// Configures the stages, `#define`s and include directories of a program, e.g.
+/// `ShaderBuilder::new().vertex("a.vs").fragment("a.fs").define_value("NUM_LIGHTS", 4).build()`
+#[derive(Clone)]
+pub struct ShaderBuilder {
+    stages: Vec<(GLenum, String)>,
+    defines: Vec<(String, String)>,
+    includeDirs: Vec<PathBuf>,
+}
+
+#[allow(dead_code)]
+impl ShaderBuilder {
+    pub fn new() -> ShaderBuilder {
+        ShaderBuilder {
+            stages: Vec::new(),
+            defines: Vec::new(),
+            includeDirs: vec![PathBuf::from(preprocess::DEFAULT_INCLUDE_DIR)],
+        }
+    }
+
+    pub fn vertex(self, path: &str) -> ShaderBuilder {
+        self.stage(gl::VERTEX_SHADER, path)
+    }
+
+    pub fn fragment(self, path: &str) -> ShaderBuilder {
+        self.stage(gl::FRAGMENT_SHADER, path)
+    }
+
+    pub fn geometry(self, path: &str) -> ShaderBuilder {
+        self.stage(gl::GEOMETRY_SHADER, path)
+    }
+
+    /// Tessellation control shader (OpenGL 4.0)
+    pub fn tess_control(self, path: &str) -> ShaderBuilder {
+        self.stage(gl::TESS_CONTROL_SHADER, path)
+    }
+
+    /// Tessellation evaluation shader (OpenGL 4.0)
+    pub fn tess_evaluation(self, path: &str) -> ShaderBuilder {
+        self.stage(gl::TESS_EVALUATION_SHADER, path)
+    }
+
+    /// Compute programs consist of this single stage, see compute.rs
+    pub fn compute(self, path: &str) -> ShaderBuilder {
+        self.stage(gl::COMPUTE_SHADER, path)
+    }
+
+    /// Adds a stage of any type (e.g. `gl::TESS_CONTROL_SHADER`); stages can be added in any order
+    pub fn stage(mut self, type_: GLenum, path: &str) -> ShaderBuilder {
+        self.stages.push((type_, path.to_string()));
+        self
+    }
+
+    /// Adds `#define <name>` to all stages
+    pub fn define(self, name: &str) -> ShaderBuilder {
+        self.define_value(name, "")
+    }
+
+    /// Adds `#define <name> <value>` to all stages
+    pub fn define_value<T: fmt::Display>(mut self, name: &str, value: T) -> ShaderBuilder {
+        self.defines.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Adds a directory searched for `#include`s that aren't found next to the including file
+    pub fn include_dir<P: AsRef<Path>>(mut self, dir: P) -> ShaderBuilder {
+        self.includeDirs.push(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Compiles the stages and links them into a program. Nothing is leaked on failure.
+    /// With `--shader-cache`, a cached binary of the program is used if there is one (see program_cache.rs).
+    pub fn build(&self) -> Result<Shader, ShaderError> {
+        unsafe {
+            let (sources, files) = self.preprocessStages()?;
+            // directory and key of the cache entry
+            let cache = context::options().shader_cache.filter(|_| program_cache::is_supported()).map(|dir| {
+                let stages: Vec<_> = self.stages.iter().zip(&sources)
+                    .map(|(&(type_, _), source)| (type_, source.code.as_str())).collect();
+                (dir, program_cache::key(&stages))
+            });
+
+            let cached = cache.as_ref().and_then(|&(ref dir, key)| program_cache::load(dir, key));
+            let ID = match cached {
+                Some(ID) => ID,
+                None => {
+                    let shaders = self.compileSources(&sources)?;
+                    // shader Program
+                    let ID = gl::CreateProgram();
+                    if cache.is_some() {
+                        program_cache::prepare(ID);
+                    }
+                    if let Err(e) = link(ID, &shaders) {
+                        gl::DeleteProgram(ID);
+                        return Err(e);
+                    }
+                    if let Some((ref dir, key)) = cache {
+                        if let Err(e) = program_cache::store(dir, key, ID) {
+                            println!("{}", e);
+                        }
+                    }
+                    ID
+                }
+            };
+
+            let watch = if context::options().watch_shaders {
+                Some(RefCell::new(Watch {
+                    builder: self.clone(),
+                    files: withModified(files),
+                    lastCheck: Instant::now(),
+                    uniforms: HashMap::new(),
+                }))
+            } else {
+                None
+            };
+            Ok(Shader {
+                ID,
+                uniforms: RefCell::new(queryUniforms(ID)),
+                warned: RefCell::new(HashSet::new()),
+                watch,
+            })
+        }
+    }
+
+    /// Preprocesses and compiles all stages; on failure the already compiled ones are deleted again.
+    /// Also returns all files involved (including the `#include`d ones).
+    unsafe fn compileStages(&self) -> Result<(Vec<GLuint>, Vec<String>), ShaderError> {
+        let (sources, files) = self.preprocessStages()?;
+        Ok((self.compileSources(&sources)?, files))
+    }
+
+    /// Checks that the stages are supported and retrieves the (preprocessed) source code from filesystem.
+    /// Also returns all files involved.
+    fn preprocessStages(&self) -> Result<(Vec<preprocess::Source>, Vec<String>), ShaderError> {
+        for &(type_, _) in &self.stages {
+            checkSupported(type_)?;
+        }
+
+        let mut sources = Vec::with_capacity(self.stages.len());
+        let mut allFiles = Vec::new();
+        for &(_, ref path) in &self.stages {
+            let source = preprocess::preprocess(path, &self.defines, &self.includeDirs)?;
+            for file in &source.files {
+                if !allFiles.contains(file) {
+                    allFiles.push(file.clone());
+                }
+            }
+            sources.push(source);
+        }
+        Ok((sources, allFiles))
+    }
+
+    /// Compiles the preprocessed stages; on failure the already compiled ones are deleted again
+    unsafe fn compileSources(&self, sources: &[preprocess::Source]) -> Result<Vec<GLuint>, ShaderError> {
+        let mut shaders = Vec::with_capacity(self.stages.len());
+        for (&(type_, ref path), source) in self.stages.iter().zip(sources) {
+            let code = CString::new(source.code.as_bytes()).map_err(|_| ShaderError::Io {
+                path: path.clone(),
+                error: io::Error::new(io::ErrorKind::InvalidData, "source contains a nul byte"),
+            });
+            match code.and_then(|code| compile(type_, &code, &source.files)) {
+                Ok(shader) => shaders.push(shader),
+                Err(e) => {
+                    for shader in shaders {
+                        gl::DeleteShader(shader);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        Ok(shaders)
+    }
 }
 
 /// NOTE: mixture of `shader_s.h` and `shader_m.h` (the latter just contains
 /// a few more setters for uniforms)
 #[allow(dead_code)]
 impl Shader {
+    /// Panics with the error message if the shader can't be built, see `try_new`
     pub fn new(vertexPath: &str, fragmentPath: &str) -> Shader {
-        let mut shader = Shader { ID: 0 };
-        // 1. retrieve the vertex/fragment source code from filesystem
-        let mut vShaderFile = File::open(vertexPath)
-            .unwrap_or_else(|_| panic!("Failed to open {}", vertexPath));
-        let mut fShaderFile = File::open(fragmentPath)
-            .unwrap_or_else(|_| panic!("Failed to open {}", fragmentPath));
-        let mut vertexCode = String::new();
-        let mut fragmentCode = String::new();
-        vShaderFile
-            .read_to_string(&mut vertexCode)
-            .expect("Failed to read vertex shader");
-        fShaderFile
-            .read_to_string(&mut fragmentCode)
-            .expect("Failed to read fragment shader");
-
-        let vShaderCode = CString::new(vertexCode.as_bytes()).unwrap();
-        let fShaderCode = CString::new(fragmentCode.as_bytes()).unwrap();
-
-        // 2. compile shaders
-        unsafe {
-            // vertex shader
-            let vertex = gl::CreateShader(gl::VERTEX_SHADER);
-            gl::ShaderSource(vertex, 1, &vShaderCode.as_ptr(), ptr::null());
-            gl::CompileShader(vertex);
-            shader.checkCompileErrors(vertex, "VERTEX");
-            // fragment Shader
-            let fragment = gl::CreateShader(gl::FRAGMENT_SHADER);
-            gl::ShaderSource(fragment, 1, &fShaderCode.as_ptr(), ptr::null());
-            gl::CompileShader(fragment);
-            shader.checkCompileErrors(fragment, "FRAGMENT");
-            // shader Program
-            let ID = gl::CreateProgram();
-            gl::AttachShader(ID, vertex);
-            gl::AttachShader(ID, fragment);
-            gl::LinkProgram(ID);
-            shader.checkCompileErrors(ID, "PROGRAM");
-            // delete the shaders as they're linked into our program now and no longer necessary
-            gl::DeleteShader(vertex);
-            gl::DeleteShader(fragment);
-            shader.ID = ID;
-        }
-
-        shader
+        Shader::try_new(vertexPath, fragmentPath).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_new(vertexPath: &str, fragmentPath: &str) -> Result<Shader, ShaderError> {
+        ShaderBuilder::new().vertex(vertexPath).fragment(fragmentPath).build()
     }
 
     /// activate the shader
     /// ------------------------------------------------------------------------
     pub unsafe fn useProgram(&self) {
-        gl::UseProgram(self.ID)
+        gl::UseProgram(self.ID);
+        if self.watch.is_some() {
+            self.reloadIfChanged();
+        }
     }
 
     /// utility uniform functions
     /// ------------------------------------------------------------------------
     pub unsafe fn setBool(&self, name: &CStr, value: bool) {
-        gl::Uniform1i(gl::GetUniformLocation(self.ID, name.as_ptr()), value as i32);
+        self.set(name.to_str().unwrap(), value);
     }
     /// ------------------------------------------------------------------------
     pub unsafe fn setInt(&self, name: &CStr, value: i32) {
-        gl::Uniform1i(gl::GetUniformLocation(self.ID, name.as_ptr()), value);
+        self.set(name.to_str().unwrap(), value);
     }
     /// ------------------------------------------------------------------------
     pub unsafe fn setFloat(&self, name: &CStr, value: f32) {
-        gl::Uniform1f(gl::GetUniformLocation(self.ID, name.as_ptr()), value);
+        self.set(name.to_str().unwrap(), value);
     }
     /// ------------------------------------------------------------------------
     pub unsafe fn setVector3(&self, name: &CStr, value: &Vector3<f32>) {
-        gl::Uniform3fv(gl::GetUniformLocation(self.ID, name.as_ptr()), 1, value.as_ptr());
+        self.set(name.to_str().unwrap(), value);
     }
     /// ------------------------------------------------------------------------
     pub unsafe fn setVec3(&self, name: &CStr, x: f32, y: f32, z: f32) {
-        gl::Uniform3f(gl::GetUniformLocation(self.ID, name.as_ptr()), x, y, z);
+        self.set(name.to_str().unwrap(), vec3(x, y, z));
     }
     /// ------------------------------------------------------------------------
     pub unsafe fn setMat4(&self, name: &CStr, mat: &Matrix4<f32>) {
-        gl::UniformMatrix4fv(gl::GetUniformLocation(self.ID, name.as_ptr()), 1, gl::FALSE, mat.as_ptr());
+        self.set(name.to_str().unwrap(), mat);
     }
 
-    /// utility function for checking shader compilation/linking errors.
-    /// ------------------------------------------------------------------------
-    unsafe fn checkCompileErrors(&self, shader: u32, type_: &str) {
-        let mut success = gl::FALSE as GLint;
-        let mut infoLog = Vec::with_capacity(1024);
-        infoLog.set_len(1024 - 1); // subtract 1 to skip the trailing null character
-        if type_ != "PROGRAM" {
-            gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut success);
-            if success != gl::TRUE as GLint {
-                gl::GetShaderInfoLog(shader, 1024, ptr::null_mut(), infoLog.as_mut_ptr() as *mut GLchar);
-                println!("ERROR::SHADER_COMPILATION_ERROR of type: {}\n{}\n \
-                          -- --------------------------------------------------- -- ",
-                         type_,
-                         str::from_utf8(&infoLog).unwrap());
-            }
-        } else {
-            gl::GetProgramiv(shader, gl::LINK_STATUS, &mut success);
-            if success != gl::TRUE as GLint {
-                gl::GetProgramInfoLog(shader, 1024, ptr::null_mut(), infoLog.as_mut_ptr() as *mut GLchar);
-                println!("ERROR::PROGRAM_LINKING_ERROR of type: {}\n{}\n \
-                          -- --------------------------------------------------- -- ",
-                         type_,
-                         str::from_utf8(&infoLog).unwrap());
+    /// Sets a uniform of the current program, e.g. `shader.set("lightColors", &colors[..])` for an array.
+    /// Uses the cached location; warns once per name and leaves the uniform unchanged if it doesn't exist,
+    /// has a different type or has fewer elements than the value.
+    /// The value is remembered if the shader is watched, so it can be restored after reloading.
+    pub unsafe fn set<T: Uniform>(&self, name: &str, value: T) {
+        if let Some(location) = self.location(name, &value) {
+            uniform::apply(location, &value);
+        }
+        if let Some(ref watch) = self.watch {
+            watch.borrow_mut().uniforms.insert(name.to_string(), RecordedUniform::new(&value));
+        }
+    }
+
+    /// Location of the uniform if it exists and can be set with the given value
+    fn location<T: Uniform + ?Sized>(&self, name: &str, value: &T) -> Option<GLint> {
+        let info = match self.uniforms.borrow().get(name) {
+            Some(&info) => info,
+            None => {
+                self.warnOnce(name, || format!("uniform `{}` doesn't exist or isn't used by the shader", name));
+                return None;
             }
+        };
+        let kind = value.kind();
+        if !kind.accepts(info.type_) {
+            self.warnOnce(name, || format!("uniform `{}` is a {} and can't be set with a {:?} value", name,
+                                           uniform::type_name(info.type_).unwrap_or("sampler/image"), kind));
+            return None;
+        }
+        if value.data().1 > info.size as usize {
+            self.warnOnce(name, || format!("uniform `{}` has only {} element(s), but {} were given",
+                                           name, info.size, value.data().1));
+            return None;
+        }
+        Some(info.location)
+    }
+
+    fn warnOnce<F: FnOnce() -> String>(&self, name: &str, message: F) {
+        if self.warned.borrow_mut().insert(name.to_string()) {
+            println!("WARNING::SHADER program {}: {}", self.ID, message());
         }
     }
 
     /// Only used in 4.9 Geometry shaders - ignore until then (shader.h in original C++)
     pub fn with_geometry_shader(vertexPath: &str, fragmentPath: &str, geometryPath: &str) -> Self {
-        let mut shader = Shader { ID: 0 };
-        // 1. retrieve the vertex/fragment source code from filesystem
-        let mut vShaderFile = File::open(vertexPath)
-            .unwrap_or_else(|_| panic!("Failed to open {}", vertexPath));
-        let mut fShaderFile = File::open(fragmentPath)
-            .unwrap_or_else(|_| panic!("Failed to open {}", fragmentPath));
-        let mut gShaderFile = File::open(geometryPath)
-            .unwrap_or_else(|_| panic!("Failed to open {}", geometryPath));
-        let mut vertexCode = String::new();
-        let mut fragmentCode = String::new();
-        let mut geometryCode = String::new();
-        vShaderFile
-            .read_to_string(&mut vertexCode)
-            .expect("Failed to read vertex shader");
-        fShaderFile
-            .read_to_string(&mut fragmentCode)
-            .expect("Failed to read fragment shader");
-        gShaderFile
-            .read_to_string(&mut geometryCode)
-            .expect("Failed to read geometry shader");
-
-        let vShaderCode = CString::new(vertexCode.as_bytes()).unwrap();
-        let fShaderCode = CString::new(fragmentCode.as_bytes()).unwrap();
-        let gShaderCode = CString::new(geometryCode.as_bytes()).unwrap();
-
-        // 2. compile shaders
-        unsafe {
-            // vertex shader
-            let vertex = gl::CreateShader(gl::VERTEX_SHADER);
-            gl::ShaderSource(vertex, 1, &vShaderCode.as_ptr(), ptr::null());
-            gl::CompileShader(vertex);
-            shader.checkCompileErrors(vertex, "VERTEX");
-            // fragment Shader
-            let fragment = gl::CreateShader(gl::FRAGMENT_SHADER);
-            gl::ShaderSource(fragment, 1, &fShaderCode.as_ptr(), ptr::null());
-            gl::CompileShader(fragment);
-            shader.checkCompileErrors(fragment, "FRAGMENT");
-            // geometry shader
-            let geometry = gl::CreateShader(gl::GEOMETRY_SHADER);
-            gl::ShaderSource(geometry, 1, &gShaderCode.as_ptr(), ptr::null());
-            gl::CompileShader(geometry);
-            shader.checkCompileErrors(geometry, "GEOMETRY");
-
-            // shader Program
-            let ID = gl::CreateProgram();
-            gl::AttachShader(ID, vertex);
-            gl::AttachShader(ID, fragment);
-            gl::AttachShader(ID, geometry);
-            gl::LinkProgram(ID);
-            shader.checkCompileErrors(ID, "PROGRAM");
-            // delete the shaders as they're linked into our program now and no longer necessary
-            gl::DeleteShader(vertex);
-            gl::DeleteShader(fragment);
-            gl::DeleteShader(geometry);
-            shader.ID = ID;
-        }
-
-        shader
+        Shader::try_with_geometry_shader(vertexPath, fragmentPath, geometryPath).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_with_geometry_shader(vertexPath: &str, fragmentPath: &str, geometryPath: &str)
+                                    -> Result<Shader, ShaderError> {
+        ShaderBuilder::new().vertex(vertexPath).fragment(fragmentPath).geometry(geometryPath).build()
+    }
+
+    /// Checks the source files for changes (at most every `WATCH_INTERVAL`) and rebuilds the program.
+    /// The new stages are first linked into a separate program, so on errors the old program stays
+    /// in use and the error is printed. On success the shaders are relinked into `ID`, which keeps
+    /// the program ID stable, and the remembered uniform values and uniform block bindings are restored.
+    unsafe fn reloadIfChanged(&self) {
+        let mut watch = self.watch.as_ref().unwrap().borrow_mut();
+        if watch.lastCheck.elapsed() < WATCH_INTERVAL {
+            return;
+        }
+        watch.lastCheck = Instant::now();
+        let mut changed = false;
+        for &mut (ref path, ref mut lastModified) in &mut watch.files {
+            let time = modified(path);
+            if time != *lastModified {
+                *lastModified = time;
+                changed = true;
+            }
+        }
+        if !changed {
+            return;
+        }
+
+        let paths: Vec<_> = watch.builder.stages.iter().map(|&(_, ref path)| path.clone()).collect();
+        let shaders = match watch.builder.compileStages() {
+            Ok((shaders, files)) => {
+                // includes may have been added or removed
+                watch.files = withModified(files);
+                shaders
+            }
+            Err(e) => {
+                println!("Failed to reload {}:\n{}", paths.join(", "), e);
+                return;
+            }
+        };
+        let test = gl::CreateProgram();
+        for &shader in &shaders {
+            gl::AttachShader(test, shader);
+        }
+        gl::LinkProgram(test);
+        let mut success = gl::FALSE as GLint;
+        gl::GetProgramiv(test, gl::LINK_STATUS, &mut success);
+        if success != gl::TRUE as GLint {
+            let log = info_log(test, gl::GetProgramiv, gl::GetProgramInfoLog);
+            gl::DeleteProgram(test);
+            for &shader in &shaders {
+                gl::DeleteShader(shader);
+            }
+            println!("Failed to reload {}:\n{}", paths.join(", "), ShaderError::Link { log });
+            return;
+        }
+        gl::DeleteProgram(test);
+
+        let blockBindings = uniformBlockBindings(self.ID);
+        let mut attached = [0; 8];
+        let mut count = 0;
+        gl::GetAttachedShaders(self.ID, attached.len() as GLsizei, &mut count, attached.as_mut_ptr());
+        for &shader in &attached[..count as usize] {
+            gl::DetachShader(self.ID, shader);
+        }
+        link(self.ID, &shaders).expect("relinking a tested program failed");
+
+        *self.uniforms.borrow_mut() = queryUniforms(self.ID);
+        self.warned.borrow_mut().clear();
+        gl::UseProgram(self.ID);
+        for (name, value) in &watch.uniforms {
+            if let Some(location) = self.location(name, value) {
+                uniform::apply(location, value);
+            }
+        }
+        for (name, binding) in blockBindings {
+            let index = gl::GetUniformBlockIndex(self.ID, name.as_ptr());
+            if index != gl::INVALID_INDEX {
+                gl::UniformBlockBinding(self.ID, index, binding);
+            }
+        }
+        println!("Reloaded {}", paths.join(", "));
+    }
+}
+
+/// Links the shaders into `program` and deletes them (they stay alive as long as they're attached)
+unsafe fn link(program: GLuint, shaders: &[GLuint]) -> Result<(), ShaderError> {
+    for &shader in shaders {
+        gl::AttachShader(program, shader);
+    }
+    gl::LinkProgram(program);
+    // delete the shaders as they're linked into our program now and no longer necessary
+    for &shader in shaders {
+        gl::DeleteShader(shader);
+    }
+
+    let mut success = gl::FALSE as GLint;
+    gl::GetProgramiv(program, gl::LINK_STATUS, &mut success);
+    if success != gl::TRUE as GLint {
+        return Err(ShaderError::Link { log: info_log(program, gl::GetProgramiv, gl::GetProgramInfoLog) });
+    }
+    Ok(())
+}
+
+/// Queries the active uniforms (excluding those in uniform blocks). For arrays, all elements
+/// can be looked up as `name[i]`, the first one also as `name`.
+unsafe fn queryUniforms(program: GLuint) -> HashMap<String, UniformInfo> {
+    let mut uniforms = HashMap::new();
+    let mut count = 0;
+    gl::GetProgramiv(program, gl::ACTIVE_UNIFORMS, &mut count);
+    for index in 0..count as GLuint {
+        let mut name = vec![0u8; 256];
+        let (mut length, mut size, mut type_) = (0, 0, 0);
+        gl::GetActiveUniform(program, index, name.len() as GLsizei, &mut length, &mut size, &mut type_,
+                             name.as_mut_ptr() as *mut GLchar);
+        name.truncate(length as usize);
+        let name = String::from_utf8_lossy(&name).into_owned();
+        let location = gl::GetUniformLocation(program, CString::new(name.as_str()).unwrap().as_ptr());
+        if location == -1 {
+            continue; // member of a uniform block
+        }
+
+        uniforms.insert(name.clone(), UniformInfo { location, type_, size });
+        if name.ends_with("[0]") {
+            let base = &name[..name.len() - 3];
+            uniforms.insert(base.to_string(), UniformInfo { location, type_, size });
+            for i in 1..size {
+                let element = format!("{}[{}]", base, i);
+                let location = gl::GetUniformLocation(program, CString::new(element.as_str()).unwrap().as_ptr());
+                uniforms.insert(element, UniformInfo { location, type_, size: size - i });
+            }
+        }
+    }
+    uniforms
+}
+
+/// Names and binding points of the active uniform blocks, which are reset when a program is relinked
+unsafe fn uniformBlockBindings(program: GLuint) -> Vec<(CString, GLuint)> {
+    let mut count = 0;
+    gl::GetProgramiv(program, gl::ACTIVE_UNIFORM_BLOCKS, &mut count);
+    (0..count as GLuint).map(|index| {
+        let mut name = vec![0u8; 256];
+        let mut length = 0;
+        gl::GetActiveUniformBlockName(program, index, name.len() as GLsizei, &mut length, name.as_mut_ptr() as *mut GLchar);
+        name.truncate(length as usize);
+        let mut binding = 0;
+        gl::GetActiveUniformBlockiv(program, index, gl::UNIFORM_BLOCK_BINDING, &mut binding);
+        (CString::new(name).unwrap(), binding as GLuint)
+    }).collect()
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+fn withModified(files: Vec<String>) -> Vec<(String, Option<SystemTime>)> {
+    files.into_iter().map(|path| {
+        let time = modified(&path);
+        (path, time)
+    }).collect()
+}
+
+/// Compiles a single stage. `files` maps the source string index in the info log to a path.
+unsafe fn compile(type_: GLenum, source: &CStr, files: &[String]) -> Result<GLuint, ShaderError> {
+    let shader = gl::CreateShader(type_);
+    gl::ShaderSource(shader, 1, &source.as_ptr(), ptr::null());
+    gl::CompileShader(shader);
+
+    let mut success = gl::FALSE as GLint;
+    gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut success);
+    if success != gl::TRUE as GLint {
+        let log = info_log(shader, gl::GetShaderiv, gl::GetShaderInfoLog);
+        gl::DeleteShader(shader);
+        return Err(ShaderError::Compile { stage: stage_name(type_), log: map_log(&log, files) });
+    }
+    Ok(shader)
+}
+
+/// Fails if the stage isn't supported by the current context (neither by version nor by extension)
+fn checkSupported(type_: GLenum) -> Result<(), ShaderError> {
+    let (required, extension) = match type_ {
+        gl::COMPUTE_SHADER => ((4, 3), "GL_ARB_compute_shader"),
+        gl::TESS_CONTROL_SHADER | gl::TESS_EVALUATION_SHADER => ((4, 0), "GL_ARB_tessellation_shader"),
+        _ => return Ok(()),
+    };
+    let version = utils::gl_version();
+    if version >= required || utils::has_extension(extension) {
+        Ok(())
+    } else {
+        Err(ShaderError::Unsupported { stage: stage_name(type_), required, version })
+    }
+}
+
+fn stage_name(type_: GLenum) -> &'static str {
+    match type_ {
+        gl::VERTEX_SHADER => "VERTEX",
+        gl::FRAGMENT_SHADER => "FRAGMENT",
+        gl::GEOMETRY_SHADER => "GEOMETRY",
+        gl::COMPUTE_SHADER => "COMPUTE",
+        gl::TESS_CONTROL_SHADER => "TESS_CONTROL",
+        gl::TESS_EVALUATION_SHADER => "TESS_EVALUATION",
+        _ => "UNKNOWN",
+    }
+}
+
+/// Reads the info log of a shader or program object
+unsafe fn info_log(object: GLuint,
+                   getiv: unsafe fn(GLuint, GLenum, *mut GLint),
+                   getInfoLog: unsafe fn(GLuint, GLsizei, *mut GLsizei, *mut GLchar)) -> String {
+    let mut length = 0;
+    getiv(object, gl::INFO_LOG_LENGTH, &mut length);
+    let mut infoLog = vec![0u8; length.max(1) as usize];
+    getInfoLog(object, length, ptr::null_mut(), infoLog.as_mut_ptr() as *mut GLchar);
+    String::from_utf8_lossy(&infoLog).trim_end_matches('\0').trim_end().to_string()
+}
+
+/// Rewrites info log lines that refer to a source location to start with `path:line:`.
+/// Drivers use different formats, e.g. `0:12(5): error: ...` (Mesa), `0(12) : error ...` (NVIDIA)
+/// or `ERROR: 0:12: ...` (AMD), where the first number is the index of the source string.
+fn map_log(log: &str, files: &[String]) -> String {
+    log.lines()
+        .map(|line| match parse_location(line) {
+            Some((source, lineNumber, message)) if source < files.len() =>
+                format!("{}:{}: {}", files[source], lineNumber, message),
+            _ => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Splits a log line into source string index, line number (with column, if any) and the message
+fn parse_location(line: &str) -> Option<(usize, String, String)> {
+    let prefix = ["ERROR: ", "WARNING: "].iter().find(|prefix| line.starts_with(*prefix)).map_or("", |prefix| prefix);
+    let rest = &line[prefix.len()..];
+    let number = |s: &str| -> Option<(usize, usize)> {
+        let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or_else(|| s.len());
+        s[..end].parse().ok().map(|n| (n, end))
+    };
+
+    let (source, end) = number(rest)?;
+    let rest = &rest[end..];
+    if !rest.starts_with(':') && !rest.starts_with('(') {
+        return None;
+    }
+    let (lineNumber, end) = number(&rest[1..])?;
+    let mut rest = &rest[1 + end..];
+    let mut location = lineNumber.to_string();
+    if rest.starts_with('(') {
+        // Mesa appends the column
+        let (column, end) = number(&rest[1..])?;
+        location = format!("{}:{}", lineNumber, column);
+        rest = &rest[1 + end..];
+    }
+    let rest = rest.trim_start_matches(|c| c == ')' || c == ':' || c == ' ');
+    Some((source, location, format!("{}{}", prefix, rest)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(source: usize, line: &str, message: &str) -> Option<(usize, String, String)> {
+        Some((source, line.to_string(), message.to_string()))
+    }
+
+    #[test]
+    fn parses_mesa_location() {
+        assert_eq!(parse_location("0:12(5): error: `foo' undeclared"), location(0, "12:5", "error: `foo' undeclared"));
+    }
+
+    #[test]
+    fn parses_nvidia_location() {
+        assert_eq!(parse_location("2(12) : error C1008: undefined variable \"foo\""),
+                   location(2, "12", "error C1008: undefined variable \"foo\""));
+    }
+
+    #[test]
+    fn parses_amd_intel_location() {
+        assert_eq!(parse_location("ERROR: 0:12: 'foo' : undeclared identifier"),
+                   location(0, "12", "ERROR: 'foo' : undeclared identifier"));
+        assert_eq!(parse_location("WARNING: 1:3: extension not supported"),
+                   location(1, "3", "WARNING: extension not supported"));
+    }
+
+    #[test]
+    fn ignores_lines_without_location() {
+        assert_eq!(parse_location("ERROR: 1 compilation errors.  No code generated."), None);
+        assert_eq!(parse_location("error: linking failed"), None);
+        assert_eq!(parse_location(""), None);
+    }
+
+    #[test]
+    fn maps_source_indices_to_paths() {
+        let files = vec!["shaders/a.fs".to_string(), "shaders/common/pbr.glsl".to_string()];
+        let log = "0:3(1): error: x\nERROR: 1:7: y\n5:1(1): error: z\nerror: linking failed";
+        assert_eq!(map_log(log, &files),
+                   "shaders/a.fs:3:1: error: x\nshaders/common/pbr.glsl:7: ERROR: y\n5:1(1): error: z\nerror: linking failed");
     }
 }
@@ -0,0 +1,387 @@
+//! Offline shader validation: `cargo run --features check-shaders -- check-shaders [paths...]` checks
+//! every shader under `src/*/shaders` (or the given files/directories) without an OpenGL context, so
+//! shader breakage is also caught on machines without a GPU.
+//!
+//! Each stage is preprocessed like at runtime (see preprocess.rs) and parsed with the `glsl` crate,
+//! which only needs the `check-shaders` feature. Stages that the tutorials link into one program
+//! (found by scanning the Rust sources for shader paths) are also checked for matching interfaces:
+//! every input of a stage must be an output of the previous stage with the same name and type.
+//! Errors are reported as `path:line: message`.
+//!
+//! Without the feature the command fails, unless `--interfaces-only` is given to explicitly skip the
+//! syntax check.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use preprocess;
+
+#[cfg(feature = "check-shaders")]
+use glsl::parser::Parse;
+#[cfg(feature = "check-shaders")]
+use glsl::syntax::TranslationUnit;
+
+/// Shader file extensions in pipeline order
+const STAGES: [&str; 6] = ["vs", "tcs", "tes", "gs", "fs", "cs"];
+
+/// Checks the shaders and returns whether no errors were found
+pub fn run(args: &[String]) -> bool {
+    let interfaces_only = args.iter().any(|arg| arg == "--interfaces-only");
+    if !interfaces_only && !cfg!(feature = "check-shaders") {
+        println!("Checking the syntax requires the `check-shaders` feature (`cargo run --features check-shaders -- check-shaders`).");
+        println!("Pass `--interfaces-only` to only check the stage interfaces.");
+        return false;
+    }
+    let paths: Vec<&String> = args.iter().filter(|arg| *arg != "--interfaces-only").collect();
+    let roots: Vec<PathBuf> = if paths.is_empty() {
+        shader_dirs()
+    } else {
+        paths.into_iter().map(PathBuf::from).collect()
+    };
+    let mut files = Vec::new();
+    for root in &roots {
+        if !root.exists() {
+            println!("{} doesn't exist", root.display());
+            return false;
+        }
+        collect_shaders(root, &mut files);
+    }
+    files.sort();
+
+    let mut errors = 0;
+    let mut stages = HashMap::new();
+    for file in &files {
+        match check_stage(file, !interfaces_only) {
+            Ok(stage) => {
+                stages.insert(file.clone(), stage);
+            }
+            Err(messages) => {
+                for message in &messages {
+                    println!("{}", message);
+                }
+                errors += messages.len();
+            }
+        }
+    }
+
+    let programs = find_programs();
+    let mut checked_programs = 0;
+    for program in &programs {
+        // only programs whose stages were all parsed successfully
+        let program_stages: Vec<_> = program.iter().filter_map(|file| stages.get(file)).collect();
+        if program_stages.len() != program.len() {
+            continue;
+        }
+        checked_programs += 1;
+        for pair in program_stages.windows(2) {
+            for message in check_interface(pair[0], pair[1]) {
+                println!("{}", message);
+                errors += 1;
+            }
+        }
+    }
+
+    if interfaces_only {
+        println!("Checked the interfaces of {} programs ({} shaders, syntax not checked): {} error(s)",
+                 checked_programs, files.len(), errors);
+    } else {
+        println!("Checked {} shaders and the interfaces of {} programs: {} error(s)", files.len(), checked_programs, errors);
+    }
+    errors == 0
+}
+
+/// The `shaders` directories of all chapters
+fn shader_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<_> = fs::read_dir("src").into_iter().flat_map(|entries| entries)
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path().join("shaders"))
+        .filter(|dir| dir.is_dir())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+fn collect_shaders(path: &Path, files: &mut Vec<PathBuf>) {
+    if path.is_dir() {
+        for entry in fs::read_dir(path).into_iter().flat_map(|entries| entries).filter_map(|entry| entry.ok()) {
+            collect_shaders(&entry.path(), files);
+        }
+    } else if stage_index(path).is_some() {
+        files.push(path.to_path_buf());
+    }
+}
+
+fn stage_index(path: &Path) -> Option<usize> {
+    let extension = path.extension()?.to_str()?;
+    STAGES.iter().position(|&stage| stage == extension)
+}
+
+/// Groups of shader files that are linked into one program, in pipeline order. Found by looking for
+/// statements in the Rust sources that mention several shader paths, like `Shader::new("a.vs", "a.fs")`.
+fn find_programs() -> Vec<Vec<PathBuf>> {
+    let mut sources = Vec::new();
+    collect_rust_sources(Path::new("src"), &mut sources);
+    let mut programs = Vec::new();
+    for source in sources {
+        let code = match fs::read_to_string(&source) {
+            Ok(code) => code,
+            Err(_) => continue,
+        };
+        for statement in code.split(';') {
+            let mut program: Vec<PathBuf> = statement.split('"').skip(1).step_by(2)
+                .map(PathBuf::from)
+                .filter(|path| stage_index(path).is_some() && path.is_file())
+                .collect();
+            program.sort_by_key(|path| stage_index(path));
+            program.dedup();
+            if program.len() > 1 && !programs.contains(&program) {
+                programs.push(program);
+            }
+        }
+    }
+    programs
+}
+
+fn collect_rust_sources(dir: &Path, sources: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).into_iter().flat_map(|entries| entries).filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_rust_sources(&path, sources);
+        } else if path.extension().map_or(false, |extension| extension == "rs") {
+            sources.push(path);
+        }
+    }
+}
+
+/// A preprocessed and parsed stage
+struct Stage {
+    extension: &'static str,
+    inputs: Vec<Variable>,
+    outputs: Vec<Variable>,
+}
+
+/// An input or output of a stage (a variable or an interface block)
+struct Variable {
+    /// variable name, or block name for interface blocks
+    name: String,
+    /// type, or the member declarations for interface blocks
+    type_: String,
+    /// `path:line` of the declaration
+    location: String,
+}
+
+fn check_stage(path: &Path, syntax: bool) -> Result<Stage, Vec<String>> {
+    let display = path.to_string_lossy().into_owned();
+    let include_dirs = [PathBuf::from(preprocess::DEFAULT_INCLUDE_DIR)];
+    let source = preprocess::preprocess(&display, &[], &include_dirs).map_err(|e| vec![format!("{}: {}", display, e)])?;
+
+    // replace the `#line` directives with empty lines and remember where each line came from instead
+    let mut lines = Vec::new();
+    let mut code = String::new();
+    let (mut file, mut line) = (0, 1);
+    for text in source.code.lines() {
+        lines.push((file, line));
+        match parse_line_directive(text) {
+            Some((next_line, next_file)) => {
+                line = next_line;
+                file = next_file;
+            }
+            None => {
+                code += text;
+                line += 1;
+            }
+        }
+        code.push('\n');
+    }
+    let locate = |line: usize| -> String {
+        let (file, line) = lines.get(line.saturating_sub(1)).cloned().unwrap_or((0, line));
+        format!("{}:{}", source.files.get(file).map_or(display.as_str(), |file| file.as_str()), line)
+    };
+
+    if syntax {
+        parse(&code).map_err(|(line, message)| vec![format!("{}: {}", locate(line), message)])?;
+    }
+
+    let extension = STAGES[stage_index(path).unwrap()];
+    let (inputs, outputs) = interface(&code, &locate);
+    Ok(Stage { extension, inputs, outputs })
+}
+
+/// Parses `#line <line> <source string>`
+fn parse_line_directive(line: &str) -> Option<(usize, usize)> {
+    let mut words = line.trim_start().strip_prefix("#line")?.split_whitespace();
+    let line = words.next()?.parse().ok()?;
+    let file = words.next().and_then(|file| file.parse().ok()).unwrap_or(0);
+    Some((line, file))
+}
+
+/// Returns the line (in `code`) and message of the first syntax error
+#[cfg(feature = "check-shaders")]
+fn parse(code: &str) -> Result<(), (usize, String)> {
+    TranslationUnit::parse(code).map(|_| ()).map_err(|e| {
+        // the message contains the position like `0: at line 12:`
+        let line = e.info.split("at line ").nth(1)
+            .and_then(|rest| rest.split(|c: char| !c.is_ascii_digit()).next())
+            .and_then(|line| line.parse().ok())
+            .unwrap_or(1);
+        let message = e.info.lines().filter(|line| !line.trim().is_empty()).last().unwrap_or("syntax error");
+        (line, message.trim().to_string())
+    })
+}
+
+/// Never called without the feature (`run` requires `--interfaces-only` then)
+#[cfg(not(feature = "check-shaders"))]
+fn parse(_code: &str) -> Result<(), (usize, String)> {
+    unreachable!("syntax checking requires the `check-shaders` feature")
+}
+
+/// Qualifiers that can precede `in`/`out` and don't matter for matching the interfaces
+const IGNORED_QUALIFIERS: [&str; 9] = ["flat", "smooth", "noperspective", "centroid", "sample", "patch",
+                                       "invariant", "precise", "highp"];
+
+/// Finds the global `in` and `out` declarations
+fn interface(code: &str, locate: &dyn Fn(usize) -> String) -> (Vec<Variable>, Vec<Variable>) {
+    let tokens = tokenize(code);
+    let (mut inputs, mut outputs) = (Vec::new(), Vec::new());
+    let mut i = 0;
+    while i < tokens.len() {
+        // at the start of a global declaration or function definition
+        let mut j = i;
+        while j < tokens.len() && (IGNORED_QUALIFIERS.contains(&tokens[j].0.as_str()) || tokens[j].0 == "layout") {
+            if tokens[j].0 == "layout" {
+                j = skip_group(&tokens, j + 1);
+            } else {
+                j += 1;
+            }
+        }
+        let direction = tokens.get(j).map(|token| token.0.as_str());
+        if direction == Some("in") || direction == Some("out") {
+            let list = if direction == Some("in") { &mut inputs } else { &mut outputs };
+            j += 1;
+            if tokens.get(j + 1).map_or(false, |token| token.0 == "{") {
+                // interface block, e.g. `out VS_OUT { vec3 normal; } vs_out;`
+                let (name, line) = tokens[j].clone();
+                let end = skip_group(&tokens, j + 1);
+                let members: Vec<_> = tokens[j + 2..end - 1].iter().map(|token| token.0.as_str()).collect();
+                list.push(Variable { name, type_: format!("{{ {} }}", members.join(" ")), location: locate(line) });
+                j = end;
+            } else if tokens.get(j).map_or(false, |token| token.0 != ";") {
+                // `out vec3 a, b[2];`
+                let type_ = tokens[j].0.clone();
+                j += 1;
+                while j < tokens.len() && tokens[j].0 != ";" {
+                    let (name, line) = tokens[j].clone();
+                    j += 1;
+                    let mut array = String::new();
+                    while j < tokens.len() && tokens[j].0 != "," && tokens[j].0 != ";" {
+                        array += &tokens[j].0;
+                        j += 1;
+                    }
+                    list.push(Variable { name, type_: format!("{}{}", type_, array), location: locate(line) });
+                    if j < tokens.len() && tokens[j].0 == "," {
+                        j += 1;
+                    }
+                }
+            }
+        }
+        // skip to the end of the declaration or function
+        while j < tokens.len() && tokens[j].0 != ";" && tokens[j].0 != "{" {
+            j += if tokens[j].0 == "(" { skip_group(&tokens, j) - j } else { 1 };
+        }
+        i = if j < tokens.len() && tokens[j].0 == "{" { skip_group(&tokens, j) } else { j + 1 };
+    }
+    (inputs, outputs)
+}
+
+/// Returns the index after the group of brackets that starts at `start` (which may also be an
+/// unbracketed token, then it's just skipped)
+fn skip_group(tokens: &[(String, usize)], start: usize) -> usize {
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(start) {
+        match token.0.as_str() {
+            "(" | "{" | "[" => depth += 1,
+            ")" | "}" | "]" => depth -= 1,
+            _ => {}
+        }
+        if depth <= 0 {
+            return i + 1;
+        }
+    }
+    tokens.len()
+}
+
+/// Splits the code into identifiers/numbers and single punctuation characters with their line numbers,
+/// skipping comments and preprocessor directives
+fn tokenize(code: &str) -> Vec<(String, usize)> {
+    let mut tokens = Vec::new();
+    let mut in_comment = false;
+    for (index, line) in code.lines().enumerate() {
+        let number = index + 1;
+        if !in_comment && line.trim_start().starts_with('#') {
+            continue;
+        }
+        let mut chars = line.chars().peekable();
+        let mut word = String::new();
+        while let Some(c) = chars.next() {
+            if in_comment {
+                if c == '*' && chars.peek() == Some(&'/') {
+                    chars.next();
+                    in_comment = false;
+                }
+                continue;
+            }
+            if c.is_alphanumeric() || c == '_' || (c == '.' && !word.is_empty() && word.chars().all(|c| c.is_ascii_digit())) {
+                word.push(c);
+                continue;
+            }
+            if !word.is_empty() {
+                tokens.push((word.clone(), number));
+                word.clear();
+            }
+            if c == '/' && chars.peek() == Some(&'/') {
+                break;
+            }
+            if c == '/' && chars.peek() == Some(&'*') {
+                chars.next();
+                in_comment = true;
+            } else if !c.is_whitespace() {
+                tokens.push((c.to_string(), number));
+            }
+        }
+        if !word.is_empty() {
+            tokens.push((word, number));
+        }
+    }
+    tokens
+}
+
+/// Checks that every input of `next` is an output of `previous` with the same type
+fn check_interface(previous: &Stage, next: &Stage) -> Vec<String> {
+    next.inputs.iter().filter_map(|input| {
+        // the inputs of these stages are arrays with one element per vertex, as are the outputs of tessellation control shaders
+        let input_type = unarrayed(&input.type_, ["tcs", "tes", "gs"].contains(&next.extension));
+        match previous.outputs.iter().find(|output| output.name == input.name) {
+            None => Some(format!("{}: input `{}` isn't an output of the {} stage",
+                                 input.location, input.name, previous.extension)),
+            Some(output) => {
+                let output_type = unarrayed(&output.type_, previous.extension == "tcs");
+                if output_type != input_type {
+                    Some(format!("{}: input `{}` is declared as `{}`, but the {} stage outputs `{}` ({})",
+                                 input.location, input.name, input_type, previous.extension, output_type, output.location))
+                } else {
+                    None
+                }
+            }
+        }
+    }).collect()
+}
+
+/// Removes the last array dimension (`vec3[]` -> `vec3`)
+fn unarrayed(type_: &str, arrayed: bool) -> &str {
+    if arrayed && type_.ends_with(']') {
+        type_.rfind('[').map_or(type_, |i| &type_[..i])
+    } else {
+        type_
+    }
+}
@@ -0,0 +1,6 @@
+// converts a depth buffer value of a perspective projection back to the linear view space depth
+float LinearizeDepth(float depth, float near_plane, float far_plane)
+{
+    float z = depth * 2.0 - 1.0; // Back to NDC
+    return (2.0 * near_plane * far_plane) / (far_plane + near_plane - z * (far_plane - near_plane));
+}
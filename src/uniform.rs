@@ -0,0 +1,239 @@
+#![allow(non_snake_case)]
+
+/// Values that can be passed to `Shader::set`: scalars, cgmath vectors/points/matrices and slices
+/// (or Vecs) of them for uniform arrays. The GL type of each uniform is known from `glGetActiveUniform`,
+/// so mismatches (e.g. setting a `vec3` with a `Matrix4`) are detected before calling `glUniform*`.
+
+use std::os::raw::c_void;
+use std::slice;
+
+use gl;
+use gl::types::*;
+
+use cgmath::{Matrix2, Matrix3, Matrix4, Point2, Point3, Vector2, Vector3, Vector4};
+
+/// Component type and count of a uniform value, determines the `glUniform*` function used
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UniformKind {
+    Int(usize),
+    UInt(usize),
+    Float(usize),
+    /// square float matrix with n columns
+    Matrix(usize),
+}
+
+impl UniformKind {
+    /// Number of 32 bit components per element
+    pub fn components(self) -> usize {
+        match self {
+            UniformKind::Int(n) | UniformKind::UInt(n) | UniformKind::Float(n) => n,
+            UniformKind::Matrix(n) => n * n,
+        }
+    }
+
+    /// Whether a uniform of the given GL type (as reported by `glGetActiveUniform`) can be set with this kind
+    pub fn accepts(self, type_: GLenum) -> bool {
+        let vectorOf = |n: usize, types: [GLenum; 4]| types[n - 1] == type_;
+        let bools = [gl::BOOL, gl::BOOL_VEC2, gl::BOOL_VEC3, gl::BOOL_VEC4];
+        match self {
+            // samplers and images are set with their texture unit
+            UniformKind::Int(1) if type_name(type_).is_none() => true,
+            UniformKind::Int(n) => vectorOf(n, [gl::INT, gl::INT_VEC2, gl::INT_VEC3, gl::INT_VEC4]) || vectorOf(n, bools),
+            UniformKind::UInt(n) => vectorOf(n, [gl::UNSIGNED_INT, gl::UNSIGNED_INT_VEC2, gl::UNSIGNED_INT_VEC3,
+                                                 gl::UNSIGNED_INT_VEC4]) || vectorOf(n, bools),
+            UniformKind::Float(n) => vectorOf(n, [gl::FLOAT, gl::FLOAT_VEC2, gl::FLOAT_VEC3, gl::FLOAT_VEC4]) || vectorOf(n, bools),
+            UniformKind::Matrix(n) => vectorOf(n, [0, gl::FLOAT_MAT2, gl::FLOAT_MAT3, gl::FLOAT_MAT4]),
+        }
+    }
+}
+
+pub trait Uniform {
+    fn kind(&self) -> UniformKind;
+    /// Pointer to the first component and number of (array) elements
+    fn data(&self) -> (*const c_void, usize);
+}
+
+/// A type that is laid out as `KIND.components()` 32 bit components without padding,
+/// so that slices of it can be passed to `glUniform*v` directly
+pub unsafe trait UniformElement: Copy {
+    const KIND: UniformKind;
+}
+
+macro_rules! uniform_element {
+    ($($t:ty => $kind:expr),*) => {
+        $(unsafe impl UniformElement for $t {
+            const KIND: UniformKind = $kind;
+        }
+
+        impl Uniform for $t {
+            fn kind(&self) -> UniformKind {
+                $kind
+            }
+            fn data(&self) -> (*const c_void, usize) {
+                (self as *const $t as *const c_void, 1)
+            }
+        })*
+    }
+}
+
+uniform_element! {
+    i32 => UniformKind::Int(1),
+    Vector2<i32> => UniformKind::Int(2),
+    Vector3<i32> => UniformKind::Int(3),
+    Vector4<i32> => UniformKind::Int(4),
+    u32 => UniformKind::UInt(1),
+    Vector2<u32> => UniformKind::UInt(2),
+    Vector3<u32> => UniformKind::UInt(3),
+    Vector4<u32> => UniformKind::UInt(4),
+    f32 => UniformKind::Float(1),
+    Vector2<f32> => UniformKind::Float(2),
+    Vector3<f32> => UniformKind::Float(3),
+    Vector4<f32> => UniformKind::Float(4),
+    Point2<f32> => UniformKind::Float(2),
+    Point3<f32> => UniformKind::Float(3),
+    Matrix2<f32> => UniformKind::Matrix(2),
+    Matrix3<f32> => UniformKind::Matrix(3),
+    Matrix4<f32> => UniformKind::Matrix(4)
+}
+
+impl<T: UniformElement> Uniform for [T] {
+    fn kind(&self) -> UniformKind {
+        T::KIND
+    }
+    fn data(&self) -> (*const c_void, usize) {
+        (self.as_ptr() as *const c_void, self.len())
+    }
+}
+
+impl<T: UniformElement> Uniform for Vec<T> {
+    fn kind(&self) -> UniformKind {
+        T::KIND
+    }
+    fn data(&self) -> (*const c_void, usize) {
+        self[..].data()
+    }
+}
+
+impl<'a, T: Uniform + ?Sized> Uniform for &'a T {
+    fn kind(&self) -> UniformKind {
+        (**self).kind()
+    }
+    fn data(&self) -> (*const c_void, usize) {
+        (**self).data()
+    }
+}
+
+impl Uniform for bool {
+    fn kind(&self) -> UniformKind {
+        UniformKind::Int(1)
+    }
+    fn data(&self) -> (*const c_void, usize) {
+        let value: &'static i32 = if *self { &1 } else { &0 };
+        (value as *const i32 as *const c_void, 1)
+    }
+}
+
+/// Copy of a uniform value, kept by watched shaders to restore the uniforms after reloading
+#[derive(Clone)]
+pub struct RecordedUniform {
+    kind: UniformKind,
+    count: usize,
+    data: Vec<u32>,
+}
+
+impl RecordedUniform {
+    pub fn new<T: Uniform + ?Sized>(value: &T) -> RecordedUniform {
+        let kind = value.kind();
+        let (ptr, count) = value.data();
+        let data = unsafe { slice::from_raw_parts(ptr as *const u32, kind.components() * count) };
+        RecordedUniform { kind, count, data: data.to_vec() }
+    }
+}
+
+impl Uniform for RecordedUniform {
+    fn kind(&self) -> UniformKind {
+        self.kind
+    }
+    fn data(&self) -> (*const c_void, usize) {
+        (self.data.as_ptr() as *const c_void, self.count)
+    }
+}
+
+/// Sets the uniform at `location` of the current program
+pub unsafe fn apply<T: Uniform + ?Sized>(location: GLint, value: &T) {
+    let (ptr, count) = value.data();
+    let count = count as GLsizei;
+    match value.kind() {
+        UniformKind::Int(n) => {
+            let ptr = ptr as *const GLint;
+            match n {
+                1 => gl::Uniform1iv(location, count, ptr),
+                2 => gl::Uniform2iv(location, count, ptr),
+                3 => gl::Uniform3iv(location, count, ptr),
+                _ => gl::Uniform4iv(location, count, ptr),
+            }
+        }
+        UniformKind::UInt(n) => {
+            let ptr = ptr as *const GLuint;
+            match n {
+                1 => gl::Uniform1uiv(location, count, ptr),
+                2 => gl::Uniform2uiv(location, count, ptr),
+                3 => gl::Uniform3uiv(location, count, ptr),
+                _ => gl::Uniform4uiv(location, count, ptr),
+            }
+        }
+        UniformKind::Float(n) => {
+            let ptr = ptr as *const GLfloat;
+            match n {
+                1 => gl::Uniform1fv(location, count, ptr),
+                2 => gl::Uniform2fv(location, count, ptr),
+                3 => gl::Uniform3fv(location, count, ptr),
+                _ => gl::Uniform4fv(location, count, ptr),
+            }
+        }
+        UniformKind::Matrix(n) => {
+            let ptr = ptr as *const GLfloat;
+            match n {
+                2 => gl::UniformMatrix2fv(location, count, gl::FALSE, ptr),
+                3 => gl::UniformMatrix3fv(location, count, gl::FALSE, ptr),
+                _ => gl::UniformMatrix4fv(location, count, gl::FALSE, ptr),
+            }
+        }
+    }
+}
+
+/// GLSL name of a non-opaque uniform type; None for samplers, images and other opaque types
+pub fn type_name(type_: GLenum) -> Option<&'static str> {
+    Some(match type_ {
+        gl::FLOAT => "float",
+        gl::FLOAT_VEC2 => "vec2",
+        gl::FLOAT_VEC3 => "vec3",
+        gl::FLOAT_VEC4 => "vec4",
+        gl::DOUBLE => "double",
+        gl::DOUBLE_VEC2 => "dvec2",
+        gl::DOUBLE_VEC3 => "dvec3",
+        gl::DOUBLE_VEC4 => "dvec4",
+        gl::INT => "int",
+        gl::INT_VEC2 => "ivec2",
+        gl::INT_VEC3 => "ivec3",
+        gl::INT_VEC4 => "ivec4",
+        gl::UNSIGNED_INT => "uint",
+        gl::UNSIGNED_INT_VEC2 => "uvec2",
+        gl::UNSIGNED_INT_VEC3 => "uvec3",
+        gl::UNSIGNED_INT_VEC4 => "uvec4",
+        gl::BOOL => "bool",
+        gl::BOOL_VEC2 => "bvec2",
+        gl::BOOL_VEC3 => "bvec3",
+        gl::BOOL_VEC4 => "bvec4",
+        gl::FLOAT_MAT2 => "mat2",
+        gl::FLOAT_MAT3 => "mat3",
+        gl::FLOAT_MAT4 => "mat4",
+        gl::FLOAT_MAT2x3 => "mat2x3",
+        gl::FLOAT_MAT2x4 => "mat2x4",
+        gl::FLOAT_MAT3x2 => "mat3x2",
+        gl::FLOAT_MAT3x4 => "mat3x4",
+        gl::FLOAT_MAT4x2 => "mat4x2",
+        gl::FLOAT_MAT4x3 => "mat4x3",
+        _ => return None,
+    })
+}
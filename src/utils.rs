@@ -1,7 +1,65 @@
+use std::ffi::CStr;
+use std::os::raw::c_char;
 use std::time::SystemTime;
 
+use gl;
+
 #[allow(dead_code)]
 pub fn elapsed(start_time: &SystemTime) -> String {
     let elapsed = start_time.elapsed().unwrap();
     format!("{}s {:.*}ms", elapsed.as_secs(), 1, elapsed.subsec_nanos() as f64 / 1_000_000.0)
 }
+
+/// Version of the current OpenGL context
+#[allow(dead_code)]
+pub fn gl_version() -> (u32, u32) {
+    let (mut major, mut minor) = (0, 0);
+    unsafe {
+        gl::GetIntegerv(gl::MAJOR_VERSION, &mut major);
+        gl::GetIntegerv(gl::MINOR_VERSION, &mut minor);
+    }
+    (major as u32, minor as u32)
+}
+
+/// Renderer and version string of the current OpenGL context, e.g.
+/// `llvmpipe (LLVM 15.0.7, 256 bits), 4.5 (Core Profile) Mesa 23.2.1`
+#[allow(dead_code)]
+pub fn gl_renderer() -> String {
+    let string = |name| unsafe {
+        let string = gl::GetString(name);
+        if string.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr(string as *const c_char).to_string_lossy().into_owned()
+        }
+    };
+    format!("{}, {}", string(gl::RENDERER), string(gl::VERSION))
+}
+
+/// Whether the current OpenGL context supports the given extension, e.g. `GL_ARB_compute_shader`
+#[allow(dead_code)]
+pub fn has_extension(name: &str) -> bool {
+    unsafe {
+        let mut count = 0;
+        gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count);
+        (0..count as u32).any(|i| {
+            let extension = gl::GetStringi(gl::EXTENSIONS, i);
+            !extension.is_null() && CStr::from_ptr(extension as *const c_char).to_bytes() == name.as_bytes()
+        })
+    }
+}
+
+/// Sets up the depth test for `camera::Projection::ReversedZ` (or back to the defaults): depth is
+/// cleared to 0 and greater values pass. If the context supports `glClipControl` (OpenGL 4.5 or
+/// `GL_ARB_clip_control`), clip space depth is mapped to [0, 1] instead of [-1, 1], which keeps the
+/// precision gained by reversing the depth range. Returns whether clip control was available.
+#[allow(dead_code)]
+pub unsafe fn set_reversed_z(enabled: bool) -> bool {
+    let clip_control = gl_version() >= (4, 5) || has_extension("GL_ARB_clip_control");
+    if clip_control {
+        gl::ClipControl(gl::LOWER_LEFT, if enabled { gl::ZERO_TO_ONE } else { gl::NEGATIVE_ONE_TO_ONE });
+    }
+    gl::ClearDepth(if enabled { 0.0 } else { 1.0 });
+    gl::DepthFunc(if enabled { gl::GREATER } else { gl::LESS });
+    clip_control
+}
@@ -0,0 +1,15 @@
+//! Runs the golden-image tests of all tutorials (see src/golden.rs) in an offscreen context.
+//! Needs the `headless` feature and an EGL implementation, e.g. Mesa's llvmpipe on CI machines:
+//! `LIBGL_ALWAYS_SOFTWARE=1 cargo test --release --features headless`
+#![cfg(feature = "headless")]
+
+use std::process::Command;
+
+#[test]
+fn golden_images() {
+    let status = Command::new(env!("CARGO_BIN_EXE_learn-opengl-rs"))
+        .arg("golden")
+        .status()
+        .expect("Failed to run learn-opengl-rs");
+    assert!(status.success(), "golden image comparison failed, see target/golden/ for actual/expected/diff images");
+}
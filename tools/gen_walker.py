@@ -0,0 +1,235 @@
+#!/usr/bin/env python3
+"""Generates resources/objects/walker/walker.gltf, the skinned figure of the skeletal animation
+tutorial (8_2): a blocky character with 11 joints, a palette texture and a 1 second walk cycle.
+Buffers and the texture are embedded as data URIs, so the output is a single file.
+
+Usage: python3 tools/gen_walker.py resources/objects/walker/walker.gltf
+"""
+import base64
+import json
+import math
+import struct
+import sys
+import zlib
+
+# ---------------------------------------------------------------- skeleton
+# name, parent, local translation (bind pose has no rotations)
+joints = [
+    ("hips", None, (0.0, 1.0, 0.0)),
+    ("spine", "hips", (0.0, 0.1, 0.0)),
+    ("head", "spine", (0.0, 0.55, 0.0)),
+    ("upperArm.L", "spine", (0.27, 0.48, 0.0)),
+    ("forearm.L", "upperArm.L", (0.0, -0.3, 0.0)),
+    ("upperArm.R", "spine", (-0.27, 0.48, 0.0)),
+    ("forearm.R", "upperArm.R", (0.0, -0.3, 0.0)),
+    ("thigh.L", "hips", (0.1, -0.05, 0.0)),
+    ("shin.L", "thigh.L", (0.0, -0.45, 0.0)),
+    ("thigh.R", "hips", (-0.1, -0.05, 0.0)),
+    ("shin.R", "thigh.R", (0.0, -0.45, 0.0)),
+]
+J = {name: i for i, (name, _, _) in enumerate(joints)}
+
+def glob(name):
+    x, y, z = 0.0, 0.0, 0.0
+    while name is not None:
+        _, parent, t = joints[J[name]]
+        x, y, z = x + t[0], y + t[1], z + t[2]
+        name = parent
+    return (x, y, z)
+
+# palette cells: skin, shirt, pants, shoes
+CELLS = 4
+def uv(cell):
+    return ((cell + 0.5) / CELLS, 0.5)
+
+positions, normals, texcoords, jointsAttr, weightsAttr, indices = [], [], [], [], [], []
+
+def quad(corners, normal, cell, influences):
+    """corners counter-clockwise seen from outside; influences: per corner list of (joint, weight)"""
+    base = len(positions)
+    for c, infl in zip(corners, influences):
+        positions.append(c)
+        normals.append(normal)
+        texcoords.append(uv(cell))
+        ids = [J[n] for n, _ in infl] + [0] * (4 - len(infl))
+        ws = [w for _, w in infl] + [0.0] * (4 - len(infl))
+        jointsAttr.append(ids)
+        weightsAttr.append(ws)
+    indices.extend([base, base + 1, base + 2, base, base + 2, base + 3])
+
+def box(minc, maxc, cell, infl_of_y):
+    (x0, y0, z0), (x1, y1, z1) = minc, maxc
+    i = infl_of_y
+    # +x
+    quad([(x1, y0, z1), (x1, y0, z0), (x1, y1, z0), (x1, y1, z1)], (1, 0, 0), cell, [i(y0), i(y0), i(y1), i(y1)])
+    # -x
+    quad([(x0, y0, z0), (x0, y0, z1), (x0, y1, z1), (x0, y1, z0)], (-1, 0, 0), cell, [i(y0), i(y0), i(y1), i(y1)])
+    # +z
+    quad([(x0, y0, z1), (x1, y0, z1), (x1, y1, z1), (x0, y1, z1)], (0, 0, 1), cell, [i(y0), i(y0), i(y1), i(y1)])
+    # -z
+    quad([(x1, y0, z0), (x0, y0, z0), (x0, y1, z0), (x1, y1, z0)], (0, 0, -1), cell, [i(y0), i(y0), i(y1), i(y1)])
+    # +y
+    quad([(x0, y1, z1), (x1, y1, z1), (x1, y1, z0), (x0, y1, z0)], (0, 1, 0), cell, [i(y1)] * 4)
+    # -y
+    quad([(x0, y0, z0), (x1, y0, z0), (x1, y0, z1), (x0, y0, z1)], (0, -1, 0), cell, [i(y0)] * 4)
+
+def rigid(joint):
+    return lambda y: [(joint, 1.0)]
+
+def limb(x, top, bottom, half, upper, lower, cells, rings):
+    """a limb as a stack of boxes; around the joint between upper and lower bone the weights blend"""
+    jointY = glob(lower)[1]
+    blend = 0.06
+    def infl(y):
+        t = min(max((jointY + blend - y) / (2 * blend), 0.0), 1.0)
+        if t <= 0.0:
+            return [(upper, 1.0)]
+        if t >= 1.0:
+            return [(lower, 1.0)]
+        return [(upper, 1.0 - t), (lower, t)]
+    for k in range(len(rings) - 1):
+        y1, y0 = rings[k], rings[k + 1]
+        cell = cells[0] if y0 >= jointY - 0.001 else cells[1]
+        box((x - half, y0, -half), (x + half, y1, half), cell, infl)
+
+SKIN, SHIRT, PANTS, SHOES = 0, 1, 2, 3
+# pelvis and torso
+box((-0.18, 0.88, -0.11), (0.18, 1.08, 0.11), PANTS, rigid("hips"))
+box((-0.2, 1.08, -0.12), (0.2, 1.62, 0.12), SHIRT, rigid("spine"))
+# neck and head
+box((-0.05, 1.62, -0.05), (0.05, 1.67, 0.05), SKIN, rigid("head"))
+box((-0.12, 1.67, -0.13), (0.12, 1.93, 0.11), SKIN, rigid("head"))
+# arms: shirt sleeves down to the elbow, then skin
+for side, x in (("L", 0.27), ("R", -0.27)):
+    elbow = glob("forearm." + side)[1]
+    limb(x, 1.6, 0.98, 0.05, "upperArm." + side, "forearm." + side, (SHIRT, SKIN),
+         [1.6, 1.45, elbow + 0.06, elbow, elbow - 0.06, 1.1, 0.98])
+# legs with shoes
+for side, x in (("L", 0.1), ("R", -0.1)):
+    knee = glob("shin." + side)[1]
+    limb(x, 0.9, 0.08, 0.07, "thigh." + side, "shin." + side, (PANTS, PANTS),
+         [0.9, 0.7, knee + 0.06, knee, knee - 0.06, 0.25, 0.08])
+    box((x - 0.075, 0.0, -0.08), (x + 0.075, 0.08, 0.16), SHOES, rigid("shin." + side))
+
+# ---------------------------------------------------------------- animation (walk cycle, 1 s)
+def quat_x(deg):
+    a = math.radians(deg) / 2
+    return (math.sin(a), 0.0, 0.0, math.cos(a))
+
+def quat_y(deg):
+    a = math.radians(deg) / 2
+    return (0.0, math.sin(a), 0.0, math.cos(a))
+
+times = [0.0, 0.25, 0.5, 0.75, 1.0]
+rotations = {
+    "thigh.L": [quat_x(a) for a in (-30, 0, 30, 0, -30)],
+    "thigh.R": [quat_x(a) for a in (30, 0, -30, 0, 30)],
+    "shin.L": [quat_x(a) for a in (10, 45, 10, 5, 10)],
+    "shin.R": [quat_x(a) for a in (10, 5, 10, 45, 10)],
+    "upperArm.L": [quat_x(a) for a in (25, 0, -25, 0, 25)],
+    "upperArm.R": [quat_x(a) for a in (-25, 0, 25, 0, -25)],
+    "forearm.L": [quat_x(a) for a in (-15, -25, -35, -25, -15)],
+    "forearm.R": [quat_x(a) for a in (-35, -25, -15, -25, -35)],
+    "spine": [quat_y(a) for a in (6, 0, -6, 0, 6)],
+}
+hipsTranslation = [(0.0, y, 0.0) for y in (1.0, 1.04, 1.0, 1.04, 1.0)]
+
+# ---------------------------------------------------------------- buffer
+buf = bytearray()
+views, accessors = [], []
+
+def align():
+    while len(buf) % 4:
+        buf.append(0)
+
+def add(fmt, rows, type_, componentType, target=None, minmax=False):
+    align()
+    offset = len(buf)
+    for r in rows:
+        buf.extend(struct.pack("<" + fmt, *(r if isinstance(r, (list, tuple)) else [r])))
+    view = {"buffer": 0, "byteOffset": offset, "byteLength": len(buf) - offset}
+    if target:
+        view["target"] = target
+    views.append(view)
+    acc = {"bufferView": len(views) - 1, "componentType": componentType, "count": len(rows), "type": type_}
+    if minmax:
+        n = len(rows[0])
+        acc["min"] = [min(r[i] for r in rows) for i in range(n)]
+        acc["max"] = [max(r[i] for r in rows) for i in range(n)]
+    accessors.append(acc)
+    return len(accessors) - 1
+
+FLOAT, USHORT, UINT = 5126, 5123, 5125
+ARRAY, ELEMENTS = 34962, 34963
+aPos = add("3f", positions, "VEC3", FLOAT, ARRAY, True)
+aNorm = add("3f", normals, "VEC3", FLOAT, ARRAY)
+aUV = add("2f", texcoords, "VEC2", FLOAT, ARRAY)
+aJoints = add("4H", jointsAttr, "VEC4", USHORT, ARRAY)
+aWeights = add("4f", weightsAttr, "VEC4", FLOAT, ARRAY)
+aIdx = add("I", indices, "SCALAR", UINT, ELEMENTS)
+
+def inverse_bind(name):
+    x, y, z = glob(name)
+    # column-major translation by -global position
+    return [1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, -x, -y, -z, 1]
+aIBM = add("16f", [inverse_bind(n) for n, _, _ in joints], "MAT4", FLOAT)
+
+aTimes = add("f", times, "SCALAR", FLOAT)
+accessors[aTimes]["min"] = [times[0]]
+accessors[aTimes]["max"] = [times[-1]]
+channels, samplers = [], []
+for name, values in rotations.items():
+    out = add("4f", values, "VEC4", FLOAT)
+    samplers.append({"input": aTimes, "output": out, "interpolation": "LINEAR"})
+    channels.append({"sampler": len(samplers) - 1, "target": {"node": J[name], "path": "rotation"}})
+out = add("3f", hipsTranslation, "VEC3", FLOAT)
+samplers.append({"input": aTimes, "output": out, "interpolation": "LINEAR"})
+channels.append({"sampler": len(samplers) - 1, "target": {"node": J["hips"], "path": "translation"}})
+
+# ---------------------------------------------------------------- palette texture (PNG)
+colors = [(224, 172, 138), (52, 101, 164), (60, 60, 72), (96, 62, 30)]
+W, H = 64, 16
+raw = bytearray()
+for y in range(H):
+    raw.append(0)
+    for x in range(W):
+        raw.extend(colors[x * CELLS // W])
+def chunk(tag, data):
+    c = struct.pack(">I", len(data)) + tag + data
+    return c + struct.pack(">I", zlib.crc32(tag + data) & 0xffffffff)
+png = b"\x89PNG\r\n\x1a\n" + chunk(b"IHDR", struct.pack(">IIBBBBB", W, H, 8, 2, 0, 0, 0)) \
+    + chunk(b"IDAT", zlib.compress(bytes(raw), 9)) + chunk(b"IEND", b"")
+
+# ---------------------------------------------------------------- document
+nodes = []
+for name, parent, t in joints:
+    node = {"name": name, "translation": list(t)}
+    children = [J[n] for n, p, _ in joints if p == name]
+    if children:
+        node["children"] = children
+    nodes.append(node)
+meshNode = len(nodes)
+nodes.append({"name": "walker", "mesh": 0, "skin": 0})
+
+gltf = {
+    "asset": {"version": "2.0", "generator": "learn-opengl-rs"},
+    "scene": 0,
+    "scenes": [{"nodes": [J["hips"], meshNode]}],
+    "nodes": nodes,
+    "meshes": [{"name": "walker", "primitives": [{
+        "attributes": {"POSITION": aPos, "NORMAL": aNorm, "TEXCOORD_0": aUV, "JOINTS_0": aJoints, "WEIGHTS_0": aWeights},
+        "indices": aIdx, "material": 0}]}],
+    "materials": [{"name": "palette", "pbrMetallicRoughness": {
+        "baseColorTexture": {"index": 0}, "metallicFactor": 0.0, "roughnessFactor": 0.8}}],
+    "textures": [{"source": 0}],
+    "images": [{"uri": "data:image/png;base64," + base64.b64encode(png).decode()}],
+    "skins": [{"name": "walker", "joints": list(range(len(joints))), "inverseBindMatrices": aIBM, "skeleton": J["hips"]}],
+    "animations": [{"name": "walk", "channels": channels, "samplers": samplers}],
+    "buffers": [{"byteLength": len(buf), "uri": "data:application/octet-stream;base64," + base64.b64encode(bytes(buf)).decode()}],
+    "bufferViews": views,
+    "accessors": accessors,
+}
+with open(sys.argv[1], "w") as f:
+    json.dump(gltf, f, indent=1)
+    f.write("\n")
+print(len(positions), "vertices", len(indices) // 3, "triangles", len(buf), "bytes")